@@ -1,27 +1,27 @@
-#![allow(bare_trait_objects)]
-
 extern crate proc_macro;
 
+use gltf_codegen_toml::Generator;
 use proc_macro::TokenStream;
 use quote::quote;
-use quote::ToTokens;
+use std::env;
+use std::path::PathBuf;
 
 fn extract_documentation(attributes: &[syn::Attribute]) -> Option<String> {
-    for attr in attributes {
-        let mut stream = proc_macro2::TokenStream::new();
-        attr.path.to_tokens(&mut stream);
-        let key = stream.to_string();
-        if key.starts_with("doc") {
-            let tts = &attr.tts;
-            let doc_attr = tts.to_string();
-            if let Some(tail) = doc_attr.split("= \"").nth(1) {
-                if let Some(docs) = tail.rsplit("\"").nth(1) {
-                    return Some(docs.to_string())
-                }
-            }
-        }
+    let lines = attributes
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::NameValue(ref meta)) if meta.ident == "doc" => match meta.lit {
+                syn::Lit::Str(ref docs) => Some(docs.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
     }
-    None
 }
 
 #[proc_macro_attribute]
@@ -39,96 +39,275 @@ pub fn hello(_: TokenStream, item: TokenStream) -> TokenStream {
     result.into()
 }
 
-fn stringify(item: &quote::ToTokens) -> String {
-    let mut token_stream = proc_macro2::TokenStream::new();
-    item.to_tokens(&mut token_stream);
-    token_stream.to_string()
+/// Expands to the code generated from a directory of TOML schema files.
+///
+/// The path is relative to the directory containing the invoking crate's
+/// `Cargo.toml`. The expansion refers to items by `crate::` paths, so it must
+/// be invoked at the crate root. The crate is rebuilt when any of the schema
+/// files or their includes change; adding a new schema file requires a clean
+/// build.
+///
+/// ```ignore
+/// gltf_codegen::include_schema!("../toml/schema");
+/// ```
+#[proc_macro]
+pub fn include_schema(input: TokenStream) -> TokenStream {
+    let path: syn::LitStr = syn::parse_macro_input!(input);
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+    let generator = Generator::new(manifest_dir.join(path.value()));
+    let result = generator.generate().and_then(|source| {
+        let files = generator.dependencies()?;
+        Ok((source, files))
+    });
+    let (source, files) = match result {
+        Ok(output) => output,
+        Err(err) => {
+            let errors = err
+                .to_string()
+                .lines()
+                .map(|line| syn::Error::new(path.span(), line).to_compile_error())
+                .collect::<Vec<_>>();
+            return quote!(#(#errors)*).into();
+        },
+    };
+    let files = files.iter().map(|file| file.to_string_lossy().into_owned());
+    let items: proc_macro2::TokenStream = source.parse().expect("generated code");
+    let result = quote! {
+        #(const _: &[u8] = include_bytes!(#files);)*
+        #items
+    };
+    result.into()
 }
 
-fn is_trivial_type(ident: &syn::Ident) -> bool {
-    ["bool", "u32", "i32", "String", "RawValue"]
-        .iter()
-        .find(|x| ident == x)
-        .is_some()
+/// Options given by `#[wrapper(...)]` field attributes.
+#[derive(Default)]
+struct FieldOptions {
+    /// The setter name, if not the field name.
+    rename: Option<syn::Ident>,
+    /// Whether the field is left to its `Default` value, without a setter.
+    skip: bool,
 }
 
-fn parse_named_fields(named_fields: syn::FieldsNamed)
-    -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>)
-{
-    let mut parsed_fields = vec![];
-    let mut parsed_impls = vec![];
-    for field in named_fields.named.iter() {
-        let ident = field.ident.as_ref().unwrap();
-        let _docs = extract_documentation(&field.attrs).unwrap_or_else(|| "?".to_string());
-        let type_path = match &field.ty {
-            syn::Type::Path(type_path) => type_path,
-            _ => panic!("cannot handle this type"),
+fn parse_field_options(attributes: &[syn::Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attributes {
+        if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "wrapper" {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected `#[wrapper(...)]`")),
         };
-        let segment = type_path.path.segments.iter().last().unwrap();
-        if segment.ident == "Index" {
-            let _type_argument = match &segment.arguments {
-                syn::PathArguments::AngleBracketed(path_args) => {
-                    match path_args.args.iter().next().unwrap() {
-                        syn::GenericArgument::Type(ty) => ty,
-                        _ => panic!("malformed Index type argument"),
-                    }
+        for nested in list.nested.iter() {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::Word(word)) if word == "skip" => options.skip = true,
+                syn::NestedMeta::Meta(syn::Meta::NameValue(meta)) if meta.ident == "rename" => match meta.lit {
+                    syn::Lit::Str(ref name) => options.rename = Some(name.parse()?),
+                    ref lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
                 },
-                _ => panic!("malformed Index type"),
-            };
-            parsed_fields.push(
-                quote! {
-                    pub #ident : #type_path,
-                }
-            );
-            parsed_impls.push(
-                quote! {
-                    pub fn #ident (&mut self, index: u32) -> &mut Self {
-                        self.#ident = Index::new(index);
-                        self
-                    }
-                }
-            );
-        } else if is_trivial_type(&segment.ident) {
-            parsed_fields.push(
-                quote! {
-                    pub #ident : #type_path,
-                }
-            );
-            parsed_impls.push(
-                quote! {
-                    pub fn #ident (&mut self, #ident: #type_path ) -> &mut Self {
-                        self.#ident = #ident;
-                        self
+                _ => return Err(syn::Error::new_spanned(nested, "expected `rename = \"...\"` or `skip`")),
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Returns the name and type arguments of the last segment of a type path.
+fn type_segment(ty: &syn::Type) -> Option<(&syn::Ident, Vec<&syn::Type>)> {
+    let type_path = match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => type_path,
+        _ => return None,
+    };
+    let segment = type_path.path.segments.iter().last()?;
+    let arguments = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|argument| match argument {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    Some((&segment.ident, arguments))
+}
+
+/// Returns the type taken by setters for values of type `ty`, and the
+/// expression converting `value` of that type to `ty`, or `None` if `ty`
+/// itself is taken.
+///
+/// Enumerations are taken without `Checked`, including in arrays, as the
+/// setters generated from the TOML schema do.
+fn setter(ty: &syn::Type, value: proc_macro2::TokenStream) -> Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    match ty {
+        syn::Type::Array(array) => {
+            let (element, item) = setter(&array.elem, quote!(item))?;
+            let len = &array.len;
+            Some((quote!([#element; #len]), quote!(#value.map(|item| #item))))
+        },
+        _ => match type_segment(ty) {
+            Some((name, ref arguments)) if name == "Checked" && arguments.len() == 1 => {
+                let of = arguments[0];
+                Some((quote!(#of), quote!(::gltf_codegen_runtime::validation::Checked::Valid(#value))))
+            },
+            Some((name, ref arguments)) if name == "Vec" && arguments.len() == 1 => {
+                let (element, item) = setter(arguments[0], quote!(item))?;
+                Some((quote!(Vec<#element>), quote!(#value.into_iter().map(|item| #item).collect())))
+            },
+            _ => None,
+        },
+    }
+}
+
+/// The parts of a builder handling one field of the input struct.
+struct FieldBuilder {
+    field: proc_macro2::TokenStream,
+    setter: proc_macro2::TokenStream,
+    check: proc_macro2::TokenStream,
+    initializer: proc_macro2::TokenStream,
+}
+
+fn parse_named_fields(object: &str, named_fields: &syn::FieldsNamed) -> syn::Result<Vec<FieldBuilder>> {
+    let mut builders = vec![];
+    for field in named_fields.named.iter() {
+        let ident = field.ident.as_ref().unwrap();
+        let options = parse_field_options(&field.attrs)?;
+        if options.skip {
+            builders.push(FieldBuilder {
+                field: quote!(),
+                setter: quote!(),
+                check: quote!(),
+                initializer: quote!(#ident: Default::default(),),
+            });
+            continue;
+        }
+        let docs = extract_documentation(&field.attrs).map(|docs| {
+            let lines = docs.lines();
+            quote!(#(#[doc = #lines])*)
+        });
+        let name = options.rename.as_ref().unwrap_or(ident);
+        let ty = &field.ty;
+        let param_value = |ty: &syn::Type| setter(ty, quote!(#ident)).unwrap_or_else(|| (quote!(#ty), quote!(#ident)));
+        // Optional fields are set to `Some` value, arrays and maps are empty
+        // unless set, and every other field is required.
+        let (field_ty, param, value, check, initializer) = match type_segment(ty) {
+            Some((segment, ref arguments)) if segment == "Option" && arguments.len() == 1 => {
+                let (param, value) = param_value(arguments[0]);
+                (quote!(#ty), param, quote!(Some(#value)), quote!(), quote!(self.#ident.take()))
+            },
+            Some((segment, _)) if ["Vec", "Map", "HashMap", "BTreeMap"].iter().any(|name| segment == name) => {
+                let (param, value) = param_value(ty);
+                (quote!(#ty), param, value, quote!(), quote!(::std::mem::take(&mut self.#ident)))
+            },
+            _ => {
+                let (param, value) = param_value(ty);
+                let field_name = ident.to_string();
+                let check = quote! {
+                    if self.#ident.is_none() {
+                        return Err(::gltf_codegen_runtime::MissingField { object: #object, field: #field_name });
                     }
+                };
+                (quote!(Option<#ty>), param, quote!(Some(#value)), check, quote!(self.#ident.take().unwrap()))
+            },
+        };
+        builders.push(FieldBuilder {
+            field: quote!(#ident: #field_ty,),
+            setter: quote! {
+                #docs
+                pub fn #name(&mut self, #ident: #param) -> &mut Self {
+                    self.#ident = #value;
+                    self
                 }
-            );
-        } else {
-            panic!("unknown type `{}`", stringify(&field.ty));
-        }
+            },
+            check,
+            initializer: quote!(#ident: #initializer,),
+        });
     }
-    (parsed_fields, parsed_impls)
+    Ok(builders)
 }
 
-#[proc_macro_derive(Wrapper)]
+/// Derives a builder for a struct with named fields, named after it, e.g.
+/// `AccessorBuilder` for `Accessor`.
+///
+/// The builder has a setter per field, named after it and carrying its
+/// documentation, and a `build` method checking that required fields are
+/// set, as do the builders generated from the TOML schema. `Option` fields
+/// are optional, `Vec` and map fields are empty unless set, and every other
+/// field is required. `Checked` enumerations are set without `Checked`.
+///
+/// Fields accept `#[wrapper(rename = "name")]` to rename their setter, and
+/// `#[wrapper(skip)]` to leave them to their `Default` value without a
+/// setter.
+///
+/// The generated code refers to `gltf_codegen_runtime`, which the invoking
+/// crate must depend on.
+#[proc_macro_derive(Wrapper, attributes(wrapper))]
 pub fn derive_wrapper(input: TokenStream) -> TokenStream {
     let item: syn::ItemStruct = syn::parse_macro_input!(input);
-    let (fields, impls) = match item.fields {
-        syn::Fields::Named(named_fields) => parse_named_fields(named_fields),
-        _ => panic!("#[derive(Wrapper)] only works on structs with named fields"),
+    let named_fields = match item.fields {
+        syn::Fields::Named(ref named_fields) => named_fields,
+        _ => {
+            let message = "#[derive(Wrapper)] only works on structs with named fields";
+            return syn::Error::new_spanned(&item, message).to_compile_error().into();
+        },
+    };
+    let name = &item.ident;
+    let builders = match parse_named_fields(&name.to_string(), named_fields) {
+        Ok(builders) => builders,
+        Err(err) => return err.to_compile_error().into(),
     };
+    let vis = &item.vis;
+    let builder = syn::Ident::new(&format!("{}Builder", name), name.span());
+    let docs = format!(" Builds a [`{}`], checking that required fields are set.", name);
+    let generics = &item.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let fields = builders.iter().map(|builder| &builder.field);
+    let setters = builders.iter().map(|builder| &builder.setter);
+    let checks = builders.iter().map(|builder| &builder.check);
+    let initializers = builders.iter().map(|builder| &builder.initializer);
+    let defaults = named_fields
+        .named
+        .iter()
+        .zip(&builders)
+        .filter(|(_, builder)| !builder.field.is_empty())
+        .map(|(field, _)| field.ident.as_ref().unwrap());
 
     let result = quote! {
-        #[derive(Debug)]
-        struct GeneratedStruct {
+        #[doc = #docs]
+        #vis struct #builder #generics #where_clause {
             #(
                 #fields
             )*
         }
 
-        impl GeneratedStruct {
+        impl #impl_generics Default for #builder #ty_generics #where_clause {
+            fn default() -> Self {
+                #builder {
+                    #( #defaults: Default::default(), )*
+                }
+            }
+        }
+
+        impl #impl_generics #builder #ty_generics #where_clause {
+            /// Returns a builder with no fields set.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
             #(
-                #impls
+                #setters
             )*
+
+            /// Builds the object, leaving the builder empty.
+            ///
+            /// Fails if a required field is unset, leaving the builder unchanged.
+            pub fn build(&mut self) -> Result<#name #ty_generics, ::gltf_codegen_runtime::MissingField> {
+                #( #checks )*
+                Ok(#name {
+                    #( #initializers )*
+                })
+            }
         }
     };
     result.into()
@@ -1,28 +1,67 @@
 use gltf_codegen::Wrapper;
-use std::marker;
+use gltf_codegen_runtime::validation::Checked;
+use gltf_codegen_runtime::{serde_json, Index, Map, MissingField};
 
-#[derive(Debug)]
 struct Buffer;
 
-#[derive(Debug)]
-struct Index<T>(u32, marker::PhantomData<T>);
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    ArrayBuffer,
+    ElementArrayBuffer,
+}
 
-impl<T> Index<T> {
-    pub fn new(index: u32) -> Self {
-        Index(index, marker::PhantomData)
-    }
+#[derive(Debug, PartialEq, Wrapper)]
+struct Sparse {
+    pub count: u32,
 }
 
 /// Documentation here.
 #[allow(dead_code)]
 #[derive(Debug, Wrapper)]
 struct InputStruct {
+    /// The parent buffer.
     pub buffer: Index<Buffer>,
-    pub foo: u32,
+    pub count: u32,
+    pub stride: Option<u32>,
+    pub min: Option<Vec<f32>>,
+    pub translation: [f32; 3],
+    pub target: Option<Checked<Target>>,
+    pub targets: Vec<Checked<Target>>,
+    pub attributes: Map<String, Index<Buffer>>,
+    pub sparse: Option<Sparse>,
+    pub extras: Option<serde_json::Value>,
+    #[wrapper(rename = "normalize")]
+    pub normalized: bool,
+    #[wrapper(skip)]
+    pub cache: Vec<u8>,
 }
 
 #[test]
-fn works() {
-    let generated = GeneratedStruct { buffer: Index::new(123), foo: 456 };
-    println!("{:#?}", generated);
+fn builds_every_field_shape() {
+    let mut builder = InputStructBuilder::new();
+    builder
+        .buffer(Index::new(123))
+        .count(456)
+        .min(vec![0.0])
+        .target(Target::ArrayBuffer)
+        .targets(vec![Target::ElementArrayBuffer])
+        .sparse(SparseBuilder::new().count(2).build().unwrap())
+        .normalize(true);
+    let err = builder.build().unwrap_err();
+    assert_eq!(err, MissingField { object: "InputStruct", field: "translation" });
+
+    let built = builder.translation([1.0, 2.0, 3.0]).build().unwrap();
+    assert_eq!(built.buffer.value(), 123);
+    assert_eq!(built.count, 456);
+    assert_eq!(built.stride, None);
+    assert_eq!(built.min, Some(vec![0.0]));
+    assert!(matches!(built.target, Some(Checked::Valid(Target::ArrayBuffer))));
+    assert!(matches!(built.targets[..], [Checked::Valid(Target::ElementArrayBuffer)]));
+    assert!(built.attributes.is_empty());
+    assert_eq!(built.sparse, Some(Sparse { count: 2 }));
+    assert!(built.normalized);
+    assert!(built.cache.is_empty());
+
+    // Building leaves the builder empty.
+    assert_eq!(builder.build().unwrap_err().field, "buffer");
 }
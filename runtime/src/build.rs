@@ -0,0 +1,51 @@
+use crate::OutOfRange;
+use std::{error, fmt};
+
+/// A required field left unset when building an object.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MissingField {
+    /// The type path of the object, e.g. `accessor::Accessor`.
+    pub object: &'static str,
+    /// The name of the field.
+    pub field: &'static str,
+}
+
+impl fmt::Display for MissingField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "missing required field `{}` of `{}`", self.field, self.object)
+    }
+}
+
+impl error::Error for MissingField {}
+
+/// An error building a document.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BuildError {
+    /// A required field of the root object is unset.
+    MissingField(MissingField),
+    /// An index refers past the end of the objects pushed to its array.
+    OutOfRange(OutOfRange),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::MissingField(ref err) => err.fmt(f),
+            BuildError::OutOfRange(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
+impl From<MissingField> for BuildError {
+    fn from(err: MissingField) -> Self {
+        BuildError::MissingField(err)
+    }
+}
+
+impl From<OutOfRange> for BuildError {
+    fn from(err: OutOfRange) -> Self {
+        BuildError::OutOfRange(err)
+    }
+}
@@ -0,0 +1,27 @@
+//! Comparison of floating point values read from JSON.
+
+/// Returns `true` if `a` and `b` are equal within a small relative tolerance.
+///
+/// Used to decide whether a field holds its default value.
+pub fn approx_eq(a: f32, b: f32) -> bool {
+    let tolerance = f32::EPSILON * a.abs().max(b.abs()).max(1.0);
+    (a - b).abs() <= tolerance
+}
+
+/// Returns `true` if `a` and `b` are equal within a small relative tolerance.
+///
+/// As [`approx_eq`], for `f64` values.
+pub fn approx_eq_f64(a: f64, b: f64) -> bool {
+    let tolerance = f64::EPSILON * a.abs().max(b.abs()).max(1.0);
+    (a - b).abs() <= tolerance
+}
+
+/// Returns `true` if two `f32` expressions are approximately equal.
+///
+/// See [`float::approx_eq`](crate::float::approx_eq).
+#[macro_export]
+macro_rules! approx_eq {
+    ($a:expr, $b:expr) => {
+        $crate::float::approx_eq($a, $b)
+    };
+}
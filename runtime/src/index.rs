@@ -0,0 +1,125 @@
+use serde::{de, ser};
+use std::{cmp, error, fmt, hash, marker};
+
+/// Typed index into a top-level array of a glTF document.
+///
+/// Serialized as a plain `u32`.
+pub struct Index<T>(u32, marker::PhantomData<fn() -> T>);
+
+impl<T> Index<T> {
+    /// Creates a new index.
+    pub fn new(value: u32) -> Self {
+        Index(value, marker::PhantomData)
+    }
+
+    /// Returns the internal value.
+    pub fn value(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// Returns `self` if it refers to one of `count` objects.
+    pub fn check(self, count: usize) -> Result<Self, OutOfRange> {
+        if self.value() < count {
+            Ok(self)
+        } else {
+            Err(OutOfRange { index: self.value(), count })
+        }
+    }
+}
+
+/// Looks up objects of type `T` by index.
+pub trait Get<T> {
+    /// Returns the object at `index`, if it exists.
+    fn get(&self, index: Index<T>) -> Option<&T>;
+
+    /// Returns the object at `index` mutably, if it exists.
+    fn get_mut(&mut self, index: Index<T>) -> Option<&mut T>;
+}
+
+/// Counts the objects of type `T` that indices may refer to.
+pub trait Count<T> {
+    /// Returns the number of objects.
+    fn count(&self) -> usize;
+}
+
+/// An index past the end of the array it refers to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutOfRange {
+    /// The offending index.
+    pub index: usize,
+    /// The number of objects in the array.
+    pub count: usize,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {} is out of range for {} objects", self.index, self.count)
+    }
+}
+
+impl error::Error for OutOfRange {}
+
+// The impls below are written by hand to avoid the `T: Trait` bounds that
+// `#[derive]` would add.
+
+impl<T> Clone for Index<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Index<T> {}
+
+impl<T> fmt::Debug for Index<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Index({})", self.0)
+    }
+}
+
+impl<T> fmt::Display for Index<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<T> PartialEq for Index<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for Index<T> {}
+
+impl<T> PartialOrd for Index<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Index<T> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T> hash::Hash for Index<T> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<T> ser::Serialize for Index<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        serializer.serialize_u32(self.0)
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for Index<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        u32::deserialize(deserializer).map(Index::new)
+    }
+}
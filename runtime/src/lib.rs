@@ -0,0 +1,24 @@
+//! Runtime support for the code generated by `gltf-codegen-toml`.
+//!
+//! Generated code refers to this crate by absolute path, so depending on it is
+//! sufficient; `serde` and `serde_json` are re-exported for the same reason.
+//!
+//! The generated structs implement [`validation::Validate`], which checks the
+//! numeric and array constraints declared in the schema.
+//!
+//! With the `lossless` feature, unrecognized enum values are kept rather than
+//! discarded; see [`validation::Checked`].
+
+pub use serde;
+pub use serde_json;
+
+mod build;
+mod index;
+
+pub mod float;
+pub mod map;
+pub mod validation;
+
+pub use build::{BuildError, MissingField};
+pub use index::{Count, Get, Index, OutOfRange};
+pub use map::Map;
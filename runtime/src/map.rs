@@ -0,0 +1,137 @@
+use serde::{de, ser};
+use std::iter::FromIterator;
+use std::{fmt, marker, slice};
+
+/// JSON object with typed keys, e.g. the attributes of a mesh primitive.
+///
+/// Entries are kept in document order, so that serialization is
+/// deterministic and round-trips without reordering. Deserializing keeps
+/// every entry, including those whose keys compare equal, e.g. repeated keys,
+/// or unrecognized enumeration keys without the `lossless` feature. Lookups
+/// find the first of them.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Map<K, V>(Vec<(K, V)>);
+
+impl<K, V> Map<K, V> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Map(Vec::new())
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Visits the entries in document order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.0.iter())
+    }
+}
+
+impl<K: PartialEq, V> Map<K, V> {
+    /// Returns the value of the first entry for `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Sets the value of the first entry for `key`, returning the previous
+    /// value, if any.
+    ///
+    /// New keys are appended.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => Some(std::mem::replace(v, value)),
+            None => {
+                self.0.push((key, value));
+                None
+            },
+        }
+    }
+
+    /// Removes the first entry for `key`, returning its value, if any.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let position = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(position).1)
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl<K: PartialEq, V> FromIterator<(K, V)> for Map<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Map<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Visits the entries of a `Map` in document order.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, K, V>(slice::Iter<'a, (K, V)>);
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K: ser::Serialize, V: ser::Serialize> ser::Serialize for Map<K, V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, K, V> de::Deserialize<'de> for Map<K, V>
+    where K: de::Deserialize<'de>, V: de::Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        struct Visitor<K, V>(marker::PhantomData<(K, V)>);
+        impl<'de, K, V> de::Visitor<'de> for Visitor<K, V>
+            where K: de::Deserialize<'de>, V: de::Deserialize<'de>
+        {
+            type Value = Map<K, V>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an object")
+            }
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+                where A: de::MapAccess<'de>
+            {
+                let mut entries = Vec::new();
+                while let Some(entry) = access.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Map(entries))
+            }
+        }
+        deserializer.deserialize_map(Visitor(marker::PhantomData))
+    }
+}
@@ -0,0 +1,306 @@
+//! Deserialization of closed sets of JSON values, and checks of the
+//! constraints declared in the schema.
+
+use serde::{de, ser};
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::{error, fmt};
+
+/// The result of deserializing a value from a closed set.
+///
+/// Unrecognized values deserialize as `Invalid` rather than failing, so that
+/// the rest of the document remains readable. With the `lossless` feature
+/// they deserialize as `Unrecognized` instead, and serialize back unchanged.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Checked<T> {
+    /// The value was recognized.
+    Valid(T),
+    /// The value was not recognized, or was neither an integer nor a string.
+    Invalid,
+    /// The value was not recognized and is kept as is.
+    ///
+    /// Only produced with the `lossless` feature.
+    Unrecognized(Unrecognized),
+}
+
+/// A JSON value outside of a closed set.
+///
+/// Also the error type of the generated `TryFrom` and `FromStr` impls.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Unrecognized {
+    /// An unrecognized integer value.
+    Integer(u64),
+    /// An unrecognized string value.
+    String(String),
+    /// An unrecognized number that is negative or not an integer.
+    Number(serde_json::Number),
+}
+
+impl fmt::Display for Unrecognized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Unrecognized::Integer(value) => write!(f, "unrecognized value {}", value),
+            Unrecognized::String(ref value) => write!(f, "unrecognized value {:?}", value),
+            Unrecognized::Number(ref value) => write!(f, "unrecognized value {}", value),
+        }
+    }
+}
+
+impl error::Error for Unrecognized {}
+
+impl<T> Checked<T> {
+    /// Converts from `&Checked<T>` to `Checked<&T>`.
+    pub fn as_ref(&self) -> Checked<&T> {
+        match *self {
+            Checked::Valid(ref item) => Checked::Valid(item),
+            Checked::Invalid => Checked::Invalid,
+            Checked::Unrecognized(ref value) => Checked::Unrecognized(value.clone()),
+        }
+    }
+
+    /// Returns `true` if the value was recognized.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Checked::Valid(_) => true,
+            Checked::Invalid | Checked::Unrecognized(_) => false,
+        }
+    }
+
+    /// Returns the recognized value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value was not recognized.
+    pub fn unwrap(self) -> T {
+        match self {
+            Checked::Valid(item) => item,
+            Checked::Invalid => panic!("attempted to unwrap an invalid item"),
+            Checked::Unrecognized(value) => panic!("attempted to unwrap an item with an {}", value),
+        }
+    }
+
+    /// Returns the recognized value, if any.
+    pub fn ok(self) -> Option<T> {
+        match self {
+            Checked::Valid(item) => Some(item),
+            Checked::Invalid | Checked::Unrecognized(_) => None,
+        }
+    }
+}
+
+/// A closed set of JSON values, deserialized as `Checked<Self>`.
+///
+/// Implemented by the generated enums.
+pub trait Enum: Sized {
+    /// Returns the item encoded by the JSON integer `value`, if any.
+    fn from_integer(_value: u64) -> Option<Self> {
+        None
+    }
+
+    /// Returns the item encoded by the JSON string `value`, if any.
+    fn from_string(_value: &str) -> Option<Self> {
+        None
+    }
+}
+
+impl<T: ser::Serialize> ser::Serialize for Checked<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        match *self {
+            Checked::Valid(ref item) => item.serialize(serializer),
+            Checked::Invalid => Err(ser::Error::custom("invalid item")),
+            Checked::Unrecognized(Unrecognized::Integer(value)) => serializer.serialize_u64(value),
+            Checked::Unrecognized(Unrecognized::String(ref value)) => serializer.serialize_str(value),
+            Checked::Unrecognized(Unrecognized::Number(ref value)) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: Enum> de::Deserialize<'de> for Checked<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        struct Visitor<T>(PhantomData<T>);
+        impl<'de, T: Enum> de::Visitor<'de> for Visitor<T> {
+            type Value = Checked<T>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an integer or string")
+            }
+            fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+                where E: de::Error
+            {
+                let item = T::from_integer(value);
+                Ok(item.map_or_else(|| unrecognized(Unrecognized::Integer(value)), Checked::Valid))
+            }
+            fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+                where E: de::Error
+            {
+                match u64::try_from(value) {
+                    Ok(value) => self.visit_u64(value),
+                    Err(_) => Ok(unrecognized(Unrecognized::Number(value.into()))),
+                }
+            }
+            fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E>
+                where E: de::Error
+            {
+                // JSON has no infinite or NaN numbers, so only other formats
+                // produce values without a `Number`.
+                Ok(serde_json::Number::from_f64(value).map_or(Checked::Invalid, |value| {
+                    unrecognized(Unrecognized::Number(value))
+                }))
+            }
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+                where E: de::Error
+            {
+                let item = T::from_string(value);
+                Ok(item.map_or_else(|| unrecognized(Unrecognized::String(value.to_string())), Checked::Valid))
+            }
+        }
+        deserializer.deserialize_any(Visitor(PhantomData))
+    }
+}
+
+/// Returns the `Checked` value for an unrecognized value.
+#[cfg(feature = "lossless")]
+fn unrecognized<T>(value: Unrecognized) -> Checked<T> {
+    Checked::Unrecognized(value)
+}
+
+/// Returns the `Checked` value for an unrecognized value.
+#[cfg(not(feature = "lossless"))]
+fn unrecognized<T>(_value: Unrecognized) -> Checked<T> {
+    Checked::Invalid
+}
+
+/// Location of a value in a JSON document, e.g. `meshes[0].primitives`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Path(String);
+
+impl Path {
+    /// Returns the path of the document root.
+    pub fn new() -> Self {
+        Path(String::new())
+    }
+
+    /// Returns the path of the property `name` of the object at `self`.
+    pub fn field(&self, name: &str) -> Self {
+        if self.0.is_empty() {
+            Path(name.to_string())
+        } else {
+            Path(format!("{}.{}", self.0, name))
+        }
+    }
+
+    /// Returns the path of the item `index` of the array at `self`.
+    pub fn index(&self, index: usize) -> Self {
+        Path(format!("{}[{}]", self.0, index))
+    }
+
+    /// Returns the path as a string, which is empty for the document root.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A schema constraint not met by a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Violation {
+    /// The number is less than the `minimum`.
+    Minimum(f64),
+    /// The number is not greater than the `exclusiveMinimum`.
+    ExclusiveMinimum(f64),
+    /// The number is greater than the `maximum`.
+    Maximum(f64),
+    /// The array has fewer than `minItems` items.
+    MinItems(usize),
+    /// The array has more than `maxItems` items.
+    MaxItems(usize),
+    /// The array item is equal to an earlier one, despite `uniqueItems`.
+    UniqueItems,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Minimum(bound) => write!(f, "must be at least {}", bound),
+            Violation::ExclusiveMinimum(bound) => write!(f, "must be greater than {}", bound),
+            Violation::Maximum(bound) => write!(f, "must be at most {}", bound),
+            Violation::MinItems(bound) => write!(f, "must have at least {} items", bound),
+            Violation::MaxItems(bound) => write!(f, "must have at most {} items", bound),
+            Violation::UniqueItems => write!(f, "must not repeat an earlier item"),
+        }
+    }
+}
+
+/// Checks the constraints declared in the schema.
+///
+/// Implemented by the generated structs, which check their own fields and
+/// those of the objects they contain.
+pub trait Validate {
+    /// Calls `report` for every violation, given that `self` is located at
+    /// `path`.
+    fn validate<R>(&self, path: &Path, report: &mut R)
+        where R: FnMut(&Path, Violation);
+
+    /// Returns every violation, located relative to `self`.
+    fn violations(&self) -> Vec<(Path, Violation)> {
+        let mut violations = vec![];
+        self.validate(&Path::new(), &mut |path: &Path, violation| violations.push((path.clone(), violation)));
+        violations
+    }
+}
+
+/// Bounds on a number.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Range {
+    /// Inclusive lower bound.
+    pub minimum: Option<f64>,
+    /// Exclusive lower bound.
+    pub exclusive_minimum: Option<f64>,
+    /// Inclusive upper bound.
+    pub maximum: Option<f64>,
+}
+
+impl Range {
+    /// Returns the first bound not met by `value`, if any.
+    pub fn check(&self, value: f64) -> Option<Violation> {
+        match (self.minimum, self.exclusive_minimum, self.maximum) {
+            (Some(bound), _, _) if value < bound => Some(Violation::Minimum(bound)),
+            (_, Some(bound), _) if value <= bound => Some(Violation::ExclusiveMinimum(bound)),
+            (_, _, Some(bound)) if value > bound => Some(Violation::Maximum(bound)),
+            _ => None,
+        }
+    }
+}
+
+/// Bounds on the number of items in an array.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Length {
+    /// Inclusive lower bound.
+    pub min_items: Option<usize>,
+    /// Inclusive upper bound.
+    pub max_items: Option<usize>,
+}
+
+impl Length {
+    /// Returns the bound not met by `len`, if any.
+    pub fn check(&self, len: usize) -> Option<Violation> {
+        match (self.min_items, self.max_items) {
+            (Some(bound), _) if len < bound => Some(Violation::MinItems(bound)),
+            (_, Some(bound)) if len > bound => Some(Violation::MaxItems(bound)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the indices of the items equal to an earlier item.
+pub fn duplicates<T: PartialEq>(items: &[T]) -> Vec<usize> {
+    (1..items.len()).filter(|&i| items[..i].contains(&items[i])).collect()
+}
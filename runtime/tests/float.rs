@@ -0,0 +1,9 @@
+use gltf_codegen_runtime::approx_eq;
+
+#[test]
+fn compares_within_relative_tolerance() {
+    assert!(approx_eq!(1.0, 1.0 + f32::EPSILON / 2.0));
+    assert!(approx_eq!(1000.0, 1000.0 + 1000.0 * f32::EPSILON / 2.0));
+    assert!(!approx_eq!(1.0, 1.001));
+    assert!(!approx_eq!(0.0, 1e-3));
+}
@@ -0,0 +1,329 @@
+//! Compiles the code generated for `tests/schema` against the runtime crate.
+
+use gltf_codegen_runtime::serde_json;
+use gltf_codegen_runtime::validation::{Checked, Path, Unrecognized, Validate, Violation};
+use std::convert::TryFrom;
+use gltf_codegen_runtime::{Get, Index, Map, OutOfRange};
+
+gltf_codegen::include_schema!("tests/schema");
+
+#[test]
+fn deserializes_indices_and_enums() {
+    let source = r#"{"sampler": 1, "source": 0}"#;
+    let texture: json::texture::Texture = serde_json::from_str(source).unwrap();
+    assert_eq!(texture.sampler, Some(Index::new(1)));
+    assert_eq!(texture.source.value(), 0);
+
+    let sampler: json::texture::Sampler = serde_json::from_str(r#"{"magFilter": 9729}"#).unwrap();
+    assert_eq!(sampler.mag_filter, Some(Checked::Valid(json::texture::MagFilter::Linear)));
+    assert_eq!(sampler.anisotropy, 1);
+    assert_eq!(sampler.wrap_s, Checked::Valid(json::texture::WrappingMode::Repeat));
+
+    #[cfg(not(feature = "lossless"))]
+    {
+        let sampler: json::texture::Sampler = serde_json::from_str(r#"{"magFilter": 1}"#).unwrap();
+        assert_eq!(sampler.mag_filter, Some(Checked::Invalid));
+    }
+
+    let image: json::texture::Image = serde_json::from_str(r#"{"mimeType": "image/png"}"#).unwrap();
+    assert_eq!(image.mime_type, Some(Checked::Valid(json::texture::MimeType::Png)));
+    let source = r#"{"source":0,"variants":{"night":2,"day":1}}"#;
+    let texture: json::texture::Texture = serde_json::from_str(source).unwrap();
+    assert_eq!(texture.variants.get(&"day".to_string()), Some(&Index::new(1)));
+    assert_eq!(serde_json::to_string(&texture).unwrap(), source);
+}
+
+#[test]
+fn looks_up_top_level_objects() {
+    let source = r#"{
+        "images": [{"uri": "a.png"}, {"uri": "b.png", "extensions": {"EXT_example": {}}}],
+        "textures": [{"source": 1}]
+    }"#;
+    let root: json::Root = serde_json::from_str(source).unwrap();
+    assert_eq!(root.get(Index::<json::texture::Image>::new(1)).and_then(|image| image.uri.as_deref()), Some("b.png"));
+    assert!(root.get(Index::<json::texture::Image>::new(2)).is_none());
+
+    let document = Document::from_json(root);
+    assert_eq!(document.images().len(), 2);
+    assert_eq!(document.samplers().len(), 0);
+    let texture = document.textures().next().unwrap();
+    let image = document.get(texture.json.source);
+    assert!(image.extensions().is_some());
+    assert!(document.try_get(Index::<json::texture::Texture>::new(1)).is_none());
+}
+
+#[test]
+fn serializes_without_defaults() {
+    let source = r#"{"magFilter": 9728, "anisotropy": 1, "wrapS": 10497}"#;
+    let sampler: json::texture::Sampler = serde_json::from_str(source).unwrap();
+    assert_eq!(serde_json::to_string(&sampler).unwrap(), r#"{"magFilter":9728}"#);
+
+    let sampler = json::texture::Sampler {
+        wrap_s: Checked::Valid(json::texture::WrappingMode::ClampToEdge),
+        ..Default::default()
+    };
+    assert_eq!(serde_json::to_string(&sampler).unwrap(), r#"{"wrapS":33071}"#);
+    assert_eq!(serde_json::to_string(&json::Root::default()).unwrap(), "{}");
+
+    #[cfg(not(feature = "lossless"))]
+    {
+        let sampler: json::texture::Sampler = serde_json::from_str(r#"{"magFilter": 0}"#).unwrap();
+        assert!(serde_json::to_string(&sampler).is_err());
+    }
+}
+
+#[test]
+fn converts_enums_from_and_to_values() {
+    use json::texture::{MagFilter, MimeType};
+
+    assert_eq!(MagFilter::Linear as u32, 9729);
+    assert!(MagFilter::Nearest < MagFilter::Linear);
+    assert_eq!(MagFilter::try_from(9728), Ok(MagFilter::Nearest));
+    assert_eq!(MagFilter::try_from(1), Err(Unrecognized::Integer(1)));
+    assert_eq!("9729".parse(), Ok(MagFilter::Linear));
+    assert_eq!("Linear".parse::<MagFilter>(), Err(Unrecognized::String("Linear".to_string())));
+    assert_eq!(MagFilter::Nearest.to_string(), "9728");
+
+    assert_eq!("image/png".parse(), Ok(MimeType::Png));
+    assert_eq!("image/gif".parse::<MimeType>(), Err(Unrecognized::String("image/gif".to_string())));
+    assert_eq!(MimeType::Jpeg.to_string(), "image/jpeg");
+}
+
+#[test]
+fn parses_pattern_enums() {
+    use json::texture::Channel;
+
+    assert_eq!("COLOR".parse(), Ok(Channel::Color));
+    assert_eq!("LAYER_12".parse(), Ok(Channel::Layer(12)));
+    assert_eq!("_depth".parse(), Ok(Channel::Custom("depth".to_string())));
+    assert!("LAYER_01".parse::<Channel>().is_err());
+    assert!("LAYER_".parse::<Channel>().is_err());
+    assert_eq!(Channel::Layer(3).to_string(), "LAYER_3");
+    assert_eq!(Channel::Color.as_str(), "COLOR");
+
+    let source = r#"{"channels":{"LAYER_0":0,"_depth":4,"COLOR":8}}"#;
+    let image: json::texture::Image = serde_json::from_str(source).unwrap();
+    assert_eq!(image.channels.get(&Checked::Valid(Channel::Custom("depth".to_string()))), Some(&4));
+    assert_eq!(serde_json::to_string(&image).unwrap(), source);
+
+    // Unrecognized keys are all kept, even where they compare equal.
+    let image: json::texture::Image = serde_json::from_str(r#"{"channels":{"ALPHA":0,"LAYER_01":4}}"#).unwrap();
+    assert_eq!(image.channels.len(), 2);
+    #[cfg(not(feature = "lossless"))]
+    assert!(image.channels.iter().all(|(key, _)| *key == Checked::Invalid));
+}
+
+#[test]
+fn reads_wide_scalars() {
+    use json::texture::WrappingMode;
+
+    let source = r#"{"byteLength":5000000000,"exposure":-1.5,"whitePoint":[1,0.95]}"#;
+    let image: json::texture::Image = serde_json::from_str(source).unwrap();
+    assert_eq!(image.byte_length, Some(5_000_000_000));
+    assert_eq!(image.white_point[0].as_u64(), Some(1));
+    assert_eq!(image.white_point[1].as_f64(), Some(0.95));
+    assert_eq!(serde_json::to_string(&image).unwrap(), source);
+
+    let sampler = json::texture::Sampler::default();
+    assert_eq!(sampler.border, -1);
+    assert_eq!(sampler.wrap, [Checked::Valid(WrappingMode::Repeat), Checked::Valid(WrappingMode::MirroredRepeat)]);
+    let source = r#"{"border":2,"wrap":[33071,33071]}"#;
+    let sampler: json::texture::Sampler = serde_json::from_str(source).unwrap();
+    assert_eq!(serde_json::to_string(&sampler).unwrap(), source);
+
+    let image: json::texture::Image = serde_json::from_str(r#"{"byteLength":0,"mipLevels":[0]}"#).unwrap();
+    assert_eq!(image.violations(), [(Path::new().field("byteLength"), Violation::Minimum(1.0))]);
+}
+
+#[test]
+fn splits_unions_by_discriminator() {
+    use json::texture::{Checker, Noise, Pattern, Procedural};
+
+    let source = r#"{"size":64,"type":"noise","noise":{"seed":7,"scale":2.0}}"#;
+    let procedural: Procedural = serde_json::from_str(source).unwrap();
+    assert_eq!(procedural.size, 64);
+    assert!(matches!(procedural.pattern, Pattern::Noise(Noise { seed: 7, .. })));
+    assert_eq!(serde_json::to_string(&procedural).unwrap(), source);
+
+    let source = r#"{"checker":{"squares":4},"type":"checker","size":16}"#;
+    let procedural: Procedural = serde_json::from_str(source).unwrap();
+    assert!(matches!(procedural.pattern, Pattern::Checker(Checker { squares: 4, .. })));
+
+    let errors = [
+        (r#"{"size":1,"checker":{}}"#, "missing field `type`"),
+        (r#"{"size":1,"type":"checker"}"#, "missing field `checker`"),
+        (r#"{"size":1,"type":"checker","checker":{},"noise":{"seed":0}}"#, "unexpected field `noise` for type `checker`"),
+        (r#"{"size":1,"type":"stripes","stripes":{}}"#, "unknown variant `stripes`"),
+    ];
+    for (source, expected) in errors {
+        let error = serde_json::from_str::<Procedural>(source).unwrap_err().to_string();
+        assert!(error.contains(expected), "{}", error);
+    }
+
+    let root: json::Root = serde_json::from_str(r#"{"procedurals":[{"size":1,"type":"checker","checker":{}}]}"#).unwrap();
+    let document = Document::from_json(root);
+    let procedural = document.procedurals().next().unwrap();
+    assert!(matches!(procedural.pattern(), texture::Pattern::Checker(_)));
+}
+
+#[test]
+fn reports_constraint_violations() {
+    let source = r#"{
+        "images": [{"mipLevels": [0, 64]}, {"mipLevels": [0, 64, 0]}],
+        "samplers": [{"anisotropy": 0}, {"anisotropy": 16}],
+        "procedurals": [{"size": 0, "type": "noise", "noise": {"seed": 1, "scale": 0.0}}]
+    }"#;
+    let root: json::Root = serde_json::from_str(source).unwrap();
+    let violations = root
+        .violations()
+        .into_iter()
+        .map(|(path, violation)| format!("{}: {}", path, violation))
+        .collect::<Vec<_>>();
+    let expected = [
+        "images[1].mipLevels[2]: must not repeat an earlier item",
+        "procedurals[0].size: must be at least 1",
+        "procedurals[0].noise.scale: must be greater than 0",
+        "samplers[0].anisotropy: must be at least 1",
+    ];
+    assert_eq!(violations, expected);
+
+    let image: json::texture::Image = serde_json::from_str(r#"{"uri": "a.png"}"#).unwrap();
+    let violations = image.violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0.as_str(), "mipLevels");
+    assert_eq!(violations[0].1, Violation::MinItems(1));
+}
+
+#[test]
+fn composes_nested_arrays() {
+    let source = r#"{"source":0,"fallbacks":{"night":[2,1]},"layers":[{"images":[1,2],"tiles":[[0,1],[1]]},{}]}"#;
+    let texture: json::texture::Texture = serde_json::from_str(source).unwrap();
+    assert_eq!(texture.fallbacks.get(&"night".to_string()), Some(&vec![Index::new(2), Index::new(1)]));
+    let layers = texture.layers.as_ref().unwrap();
+    assert_eq!(layers[0].tiles, vec![vec![0, 1], vec![1]]);
+    assert_eq!(layers[1].region, [[0.0, 0.0], [1.0, 1.0]]);
+    assert_eq!(serde_json::to_string(&texture).unwrap(), source);
+
+    let violations = texture.violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0.as_str(), "layers[1].images");
+    assert_eq!(violations[0].1, Violation::MinItems(1));
+
+    // An absent array is distinct from an empty one.
+    let texture: json::texture::Texture = serde_json::from_str(r#"{"source":0,"layers":[]}"#).unwrap();
+    assert_eq!(texture.layers, Some(vec![]));
+    let texture: json::texture::Texture = serde_json::from_str(r#"{"source":0}"#).unwrap();
+    assert_eq!(texture.layers, None);
+    assert_eq!(serde_json::to_string(&texture).unwrap(), r#"{"source":0}"#);
+}
+
+#[cfg(feature = "lossless")]
+#[test]
+fn keeps_unrecognized_values() {
+    let source = r#"{"magFilter":1}"#;
+    let sampler: json::texture::Sampler = serde_json::from_str(source).unwrap();
+    assert_eq!(sampler.mag_filter, Some(Checked::Unrecognized(Unrecognized::Integer(1))));
+    assert_eq!(serde_json::to_string(&sampler).unwrap(), source);
+
+    let source = r#"{"mimeType":"image/gif"}"#;
+    let image: json::texture::Image = serde_json::from_str(source).unwrap();
+    assert_eq!(image.mime_type, Some(Checked::Unrecognized(Unrecognized::String("image/gif".to_string()))));
+    assert_eq!(serde_json::to_string(&image).unwrap(), source);
+
+    // Wrappers return the values as they are.
+    let document = Document::from_json(serde_json::from_str(r#"{"samplers":[{"magFilter":1}]}"#).unwrap());
+    let mag_filter = document.samplers().next().unwrap().mag_filter();
+    assert_eq!(mag_filter, Some(Checked::Unrecognized(Unrecognized::Integer(1))));
+
+    // Negative and fractional numbers are kept too.
+    for source in [r#"{"magFilter":-1}"#, r#"{"magFilter":9728.5}"#] {
+        let sampler: json::texture::Sampler = serde_json::from_str(source).unwrap();
+        assert!(matches!(sampler.mag_filter, Some(Checked::Unrecognized(Unrecognized::Number(_)))));
+        assert_eq!(serde_json::to_string(&sampler).unwrap(), source);
+    }
+}
+
+#[test]
+fn edits_with_checked_indices() {
+    use json::texture::{MagFilter, WrappingMode};
+
+    let source = r#"{
+        "images": [{"uri": "a.png"}, {"uri": "b.png"}],
+        "samplers": [{}],
+        "textures": [{"source": 0, "layers": [{"images": [0]}]}],
+        "procedurals": [{"size": 1, "type": "checker", "checker": {}}]
+    }"#;
+    let mut document = Document::from_json(serde_json::from_str(source).unwrap());
+    let mut texture = document.get_mut(Index::<json::texture::Texture>::new(0));
+    texture.set_source(Index::new(1)).unwrap().set_sampler(Some(Index::new(0))).unwrap();
+    assert_eq!(texture.set_source(Index::new(2)).unwrap_err(), OutOfRange { index: 2, count: 2 });
+    assert_eq!(texture.as_json().source.value(), 1);
+    let mut fallbacks = Map::new();
+    fallbacks.insert("night".to_string(), vec![Index::new(0), Index::new(3)]);
+    assert!(texture.set_fallbacks(fallbacks).is_err());
+
+    let mut layer = texture.layers_mut(0).unwrap();
+    layer.push_to_images(Index::new(1)).unwrap();
+    assert!(layer.push_to_images(Index::new(5)).is_err());
+    assert_eq!(texture.remove_from_layers(0).unwrap().images, [Index::new(0), Index::new(1)]);
+    assert!(texture.remove_from_layers(1).is_none());
+    assert!(texture.layers_mut(0).is_none());
+
+    let image = document.push_to_images(Default::default());
+    assert_eq!(image.value(), 2);
+    document.get_mut(Index::<json::texture::Texture>::new(0)).set_source(image).unwrap();
+    document
+        .get_mut(Index::<json::texture::Sampler>::new(0))
+        .set_mag_filter(Some(MagFilter::Nearest))
+        .set_wrap([WrappingMode::ClampToEdge; 2]);
+    let sampler = document.samplers().next().unwrap();
+    assert_eq!(serde_json::to_string(sampler.json).unwrap(), r#"{"magFilter":9728,"wrap":[33071,33071]}"#);
+
+    let mut procedural = document.get_mut(Index::<json::texture::Procedural>::new(0));
+    match procedural.pattern_mut() {
+        texture::PatternMut::Checker(mut checker) => checker.set_squares(4),
+        texture::PatternMut::Noise(_) => unreachable!(),
+    };
+    let procedural = document.procedurals().next().unwrap();
+    assert!(matches!(procedural.pattern(), texture::Pattern::Checker(checker) if checker.squares() == 4));
+    assert!(document.try_get_mut(Index::<json::texture::Image>::new(3)).is_none());
+}
+
+#[test]
+fn reads_fields_through_wrappers() {
+    let source = r#"{
+        "images": [{"uri": "a.png"}],
+        "atlases": [{
+            "label": "grass",
+            "srgb": true,
+            "scale": 0.5,
+            "metadata": {"biome": "plains"},
+            "offsets": [-4, 4],
+            "transform": {"rotation": 1.5, "source": 0},
+            "mipTransform": {"offset": [0.5, 0.0]},
+            "cacheKey": "grass@1"
+        }]
+    }"#;
+    let document = Document::from_json(serde_json::from_str(source).unwrap());
+    let atlas = document.atlases().next().unwrap();
+    assert_eq!(atlas.label(), "grass");
+    assert!(atlas.srgb());
+    assert_eq!(atlas.scale().as_f64(), Some(0.5));
+    assert!(atlas.gamma().is_none());
+    assert_eq!(atlas.metadata(), Some(&serde_json::json!({"biome": "plains"})));
+    assert_eq!(atlas.offsets().copied().collect::<Vec<_>>(), [-4, 4]);
+    assert_eq!(atlas.json.cache_key.as_deref(), Some("grass@1"));
+
+    let transform = atlas.transform();
+    assert_eq!(transform.rotation(), 1.5);
+    assert_eq!(transform.offset(), [0.0, 0.0]);
+    assert_eq!(transform.source().and_then(|image| image.uri()), Some("a.png"));
+    let mip_transform = atlas.mip_transform().unwrap();
+    assert_eq!(mip_transform.offset(), [0.5, 0.0]);
+    assert!(mip_transform.source().is_none());
+
+    let atlas: json::texture::Atlas = serde_json::from_str(r#"{"label":"sand","scale":1,"transform":{}}"#).unwrap();
+    assert!(!atlas.srgb);
+    assert_eq!(atlas.metadata, None);
+    assert_eq!(atlas.cache_key, None);
+}
@@ -0,0 +1,173 @@
+//! Compiles the code generated for the glTF schema of `gltf-codegen-toml`
+//! against the runtime crate.
+
+use gltf_codegen_runtime::validation::Checked;
+use gltf_codegen_runtime::{serde_json, BuildError, Index, MissingField, OutOfRange};
+
+gltf_codegen::include_schema!("../toml/schema");
+
+#[test]
+fn refers_back_to_parents() {
+    let source = r#"{
+        "asset": {"version": "2.0"},
+        "materials": [{"pbrMetallicRoughness": {"baseColorTexture": {"index": 0}}}],
+        "meshes": [
+            {"primitives": [{"attributes": {"POSITION": 0}}]},
+            {"primitives": [{"attributes": {"POSITION": 1}}, {"attributes": {"POSITION": 2}, "mode": 1}]}
+        ],
+        "animations": [{
+            "channels": [{"sampler": 1, "target": {"node": 0, "path": "rotation"}}],
+            "samplers": [{"input": 0, "output": 1}, {"input": 2, "output": 3, "interpolation": "STEP"}]
+        }]
+    }"#;
+    let document = Document::from_json(serde_json::from_str(source).unwrap());
+    let mesh = document.meshes().nth(1).unwrap();
+    assert_eq!(mesh.index(), 1);
+    let primitive = mesh.primitives().nth(1).unwrap();
+    assert_eq!(primitive.index(), 1);
+    assert_eq!(primitive.parent().index(), 1);
+    assert_eq!(primitive.mode(), Checked::Valid(json::mesh::Mode::Lines));
+
+    // The sampler of a channel is an index into the samplers of its animation.
+    let channel = document.animations().next().unwrap().channels().next().unwrap();
+    let sampler = channel.sampler().unwrap();
+    assert_eq!(sampler.index(), 1);
+    assert_eq!(sampler.interpolation(), Checked::Valid(json::animation::Interpolation::Step));
+    assert_eq!(channel.target().parent().sampler().unwrap().index(), 1);
+
+    let pbr = document.materials().next().unwrap().pbr_metallic_roughness().unwrap();
+    let info = pbr.base_color_texture().unwrap();
+    assert!(matches!(info.parent(), texture::InfoParent::PbrMetallicRoughness(_)));
+    assert_eq!(pbr.parent().index(), 0);
+}
+
+#[test]
+fn checks_indices_into_arrays_of_the_parent() {
+    let source = r#"{
+        "asset": {"version": "2.0"},
+        "nodes": [{}],
+        "animations": [{
+            "channels": [{"sampler": 0, "target": {"node": 0, "path": "rotation"}}],
+            "samplers": [{"input": 0, "output": 1}]
+        }]
+    }"#;
+    let mut document = Document::from_json(serde_json::from_str(source).unwrap());
+    let mut animation = document.get_mut(Index::<json::animation::Animation>::new(0));
+    assert!(animation.channels_mut(0).unwrap().set_sampler(Index::new(1)).is_err());
+    let sampler = json::animation::Sampler { input: Index::new(2), ..animation.as_json().samplers[0].clone() };
+    animation.push_to_samplers(sampler);
+    animation.channels_mut(0).unwrap().set_sampler(Index::new(1)).unwrap();
+    document.asset_mut().set_generator(Some("gltf-codegen".to_string()));
+    assert_eq!(document.animations().next().unwrap().channels().next().unwrap().sampler().unwrap().index(), 1);
+    assert_eq!(document.as_json().asset.generator.as_deref(), Some("gltf-codegen"));
+}
+
+#[test]
+fn shares_owned_handles_across_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Document>();
+    assert_send_sync::<scene::NodeRef>();
+
+    let source = r#"{
+        "asset": {"version": "2.0"},
+        "accessors": [{"bufferView": 0, "componentType": 5126, "type": "VEC3"}],
+        "nodes": [{"name": "root", "children": [1]}, {"name": "child", "mesh": 0}],
+        "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}, {"attributes": {"POSITION": 1}, "mode": 1}]}],
+        "animations": [{
+            "channels": [{"sampler": 1, "target": {"node": 1, "path": "rotation"}}],
+            "samplers": [{"input": 0, "output": 1}, {"input": 2, "output": 3, "interpolation": "STEP"}]
+        }]
+    }"#;
+    let document = std::sync::Arc::new(Document::from_json(serde_json::from_str(source).unwrap()));
+    let root = document.nodes_ref().next().unwrap();
+    let child = std::thread::spawn(move || root.children().next().unwrap().unwrap())
+        .join()
+        .unwrap();
+    assert_eq!(child.index(), 1);
+    let primitive = child.mesh().unwrap().primitives().nth(1).unwrap();
+    assert_eq!(primitive.mode(), Checked::Valid(json::mesh::Mode::Lines));
+    assert_eq!(primitive.parent().index(), 0);
+    let (semantic, accessor) = primitive.parent().primitives().next().unwrap().attributes().next().unwrap();
+    assert_eq!(semantic, Checked::Valid(json::mesh::Semantic::Positions));
+    assert_eq!(accessor.unwrap().index(), 0);
+    assert!(primitive.attributes().next().unwrap().1.is_none());
+
+    let channel = document.get_ref(Index::<json::animation::Animation>::new(0)).channels().next().unwrap();
+    assert_eq!(channel.sampler().unwrap().interpolation(), Checked::Valid(json::animation::Interpolation::Step));
+    assert_eq!(channel.target().parent().sampler().unwrap().index(), 1);
+    assert!(document.try_get_ref(Index::<json::scene::Node>::new(2)).is_none());
+}
+
+#[test]
+fn builds_documents_without_json() {
+    let mut builder = DocumentBuilder::new();
+    let buffer = builder.push_to_buffers(buffer::BufferBuilder::new().byte_length(64).build().unwrap());
+    let mut view = buffer::ViewBuilder::new();
+    view.byte_length(64).target(json::buffer::Target::ArrayBuffer);
+    let err = view.build().unwrap_err();
+    assert_eq!(err, MissingField { object: "buffer::View", field: "buffer" });
+    let view = builder.push_to_buffer_views(view.buffer(buffer).build().unwrap());
+    let child = builder.push_to_nodes(scene::NodeBuilder::new().translation([1.0, 2.0, 3.0]).build().unwrap());
+    let root = builder.push_to_nodes(scene::NodeBuilder::new().children(vec![child]).build().unwrap());
+    let scene = builder.push_to_scenes(scene::SceneBuilder::new().nodes(vec![root]).build().unwrap());
+    builder.scene(scene);
+    assert_eq!(builder.build().unwrap_err().to_string(), "missing required field `asset` of `Root`");
+
+    let asset = asset::AssetBuilder::new().version("2.0".to_string()).build().unwrap();
+    let document = builder.asset(asset).build().unwrap();
+    assert_eq!(document.get(view).byte_offset(), 0);
+    assert_eq!(document.get(view).buffer().unwrap().index(), 0);
+    let root = document.default_scene().unwrap().nodes().next().unwrap().unwrap();
+    assert_eq!(root.children().next().unwrap().unwrap().translation(), [1.0, 2.0, 3.0]);
+    let json = serde_json::to_string(document.as_json()).unwrap();
+    assert!(json.contains(r#""bufferViews":[{"buffer":0,"byteLength":64,"target":34962}]"#), "{}", json);
+}
+
+#[test]
+fn checks_indices_when_building_documents() {
+    let mut builder = DocumentBuilder::new();
+    builder.asset(asset::AssetBuilder::new().version("2.0".to_string()).build().unwrap());
+    let root = builder.push_to_nodes(scene::NodeBuilder::new().children(vec![Index::new(1)]).build().unwrap());
+    builder.scene(Index::new(0));
+    assert_eq!(builder.build().unwrap_err(), BuildError::OutOfRange(OutOfRange { index: 0, count: 0 }));
+    builder.push_to_scenes(scene::SceneBuilder::new().nodes(vec![root]).build().unwrap());
+    assert_eq!(builder.build().unwrap_err(), BuildError::OutOfRange(OutOfRange { index: 1, count: 1 }));
+    builder.push_to_nodes(scene::NodeBuilder::new().build().unwrap());
+
+    // Indices into arrays of the parent are checked against them.
+    let animation = r#"{
+        "channels": [{"sampler": 1, "target": {"node": 1, "path": "rotation"}}],
+        "samplers": [{"input": 0, "output": 0}]
+    }"#;
+    builder.push_to_animations(serde_json::from_str(animation).unwrap());
+    assert_eq!(builder.build().unwrap_err(), BuildError::OutOfRange(OutOfRange { index: 1, count: 1 }));
+}
+
+#[test]
+fn reads_dangling_indices_as_none() {
+    let source = r#"{
+        "asset": {"version": "2.0"},
+        "buffers": [{"byteLength": 4}],
+        "bufferViews": [{"buffer": 1, "byteLength": 4}],
+        "nodes": [{"children": [0, 3], "mesh": 2}]
+    }"#;
+    let document = Document::from_json(serde_json::from_str(source).unwrap());
+    let view = document.buffer_views().next().unwrap();
+    assert!(view.buffer().is_none());
+    let node = document.nodes().next().unwrap();
+    assert!(node.mesh().is_none());
+    let children = node.children().map(|child| child.map(|child| child.index())).collect::<Vec<_>>();
+    assert_eq!(children, [Some(0), None]);
+
+    // Indices into arrays of the parent are looked up in them.
+    let source = r#"{
+        "asset": {"version": "2.0"},
+        "animations": [{
+            "channels": [{"sampler": 1, "target": {"node": 0, "path": "rotation"}}],
+            "samplers": [{"input": 0, "output": 1}]
+        }]
+    }"#;
+    let document = std::sync::Arc::new(Document::from_json(serde_json::from_str(source).unwrap()));
+    assert!(document.animations().next().unwrap().channels().next().unwrap().sampler().is_none());
+    assert!(document.animations_ref().next().unwrap().channels().next().unwrap().sampler().is_none());
+}
@@ -0,0 +1,28 @@
+use gltf_codegen_runtime::{serde_json, Map};
+
+#[test]
+fn keeps_document_order() {
+    let source = r#"{"TEXCOORD_0":2,"POSITION":0,"NORMAL":1}"#;
+    let mut map: Map<String, u32> = serde_json::from_str(source).unwrap();
+    let keys = map.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+    assert_eq!(keys, ["TEXCOORD_0", "POSITION", "NORMAL"]);
+    assert_eq!(serde_json::to_string(&map).unwrap(), source);
+
+    assert_eq!(map.insert("POSITION".to_string(), 3), Some(0));
+    assert_eq!(map.insert("COLOR_0".to_string(), 4), None);
+    assert_eq!(map.remove(&"TEXCOORD_0".to_string()), Some(2));
+    assert_eq!(map.get(&"POSITION".to_string()), Some(&3));
+    assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"POSITION":3,"NORMAL":1,"COLOR_0":4}"#);
+}
+
+#[test]
+fn keeps_repeated_keys() {
+    let source = r#"{"POSITION":0,"NORMAL":1,"POSITION":2}"#;
+    let mut map: Map<String, u32> = serde_json::from_str(source).unwrap();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get(&"POSITION".to_string()), Some(&0));
+    assert_eq!(serde_json::to_string(&map).unwrap(), source);
+
+    assert_eq!(map.remove(&"POSITION".to_string()), Some(0));
+    assert_eq!(map.get(&"POSITION".to_string()), Some(&2));
+}
@@ -0,0 +1,3113 @@
+//! Token stream backend shared by every front end.
+
+use crate::schema::{
+    Constraints, Definition, EnumDef, FieldDef, Literal, Meta, Payload, Repr, RootDef, StructDef, Type, UnionDef, VariantDef,
+};
+use inflections::Inflect;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use std::collections::BTreeMap;
+use std::{fs, io};
+
+type Result<T> = std::result::Result<T, std::boxed::Box<dyn std::error::Error>>;
+
+/// Generated code for a single schema definition.
+pub struct Generated {
+    /// Module path of the definition, e.g. `Some("accessor")`.
+    pub module: Option<String>,
+    /// Items for the `json` module tree.
+    pub json: TokenStream,
+    /// Items for the wrapper module tree.
+    pub wrapper: TokenStream,
+}
+
+impl Generated {
+    /// Returns the module path of the JSON items, e.g. `json::accessor`.
+    pub fn json_module(&self) -> String {
+        self.module.as_ref().map_or_else(|| "json".to_string(), |x| format!("json::{}", x))
+    }
+}
+
+/// Options affecting the generated items.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Derive macros applied to the JSON structs.
+    pub struct_derives: Vec<String>,
+    /// Derive macros applied to the JSON enums.
+    pub enum_derives: Vec<String>,
+    /// Cargo feature gating the `name` fields, if any.
+    pub names_feature: Option<String>,
+    /// Cargo feature gating the `extras` fields, if any.
+    pub extras_feature: Option<String>,
+    /// Path of the runtime support crate.
+    pub runtime: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        let strings = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Options {
+            struct_derives: strings(&["Clone", "Debug", "Deserialize", "Eq", "Hash", "PartialEq", "Serialize"]),
+            enum_derives: strings(&["Clone", "Copy", "Debug", "Eq", "Hash", "PartialEq"]),
+            names_feature: Some("names".to_string()),
+            extras_feature: Some("extras".to_string()),
+            runtime: "::gltf_codegen_runtime".to_string(),
+        }
+    }
+}
+
+/// Options resolved for a single `generate` call.
+struct Context<'a> {
+    options: &'a Options,
+    /// Path of the runtime support crate.
+    runtime: syn::Path,
+}
+
+impl<'a> Context<'a> {
+    fn new(options: &'a Options) -> Result<Self> {
+        let runtime = syn::parse_str(&options.runtime)
+            .map_err(|_| format!("invalid runtime crate path `{}`", options.runtime))?;
+        Ok(Context { options, runtime })
+    }
+
+    /// Returns a `#[derive]` attribute for `names`.
+    ///
+    /// `Deserialize` and `Serialize` refer to the serde derives re-exported by
+    /// the runtime crate.
+    fn derive(&self, names: &[String]) -> Result<TokenStream> {
+        let runtime = &self.runtime;
+        let paths = names
+            .iter()
+            .map(|name| match name.as_str() {
+                "Deserialize" => Ok(quote!(#runtime::serde::Deserialize)),
+                "Serialize" => Ok(quote!(#runtime::serde::Serialize)),
+                _ => syn::parse_str::<syn::Path>(name)
+                    .map(|path| quote!(#path))
+                    .map_err(|_| format!("invalid derive `{}`", name)),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(quote!(#[derive(#(#paths),*)]))
+    }
+
+    /// Returns the `#[derive]` attribute for a definition.
+    ///
+    /// Starts from the generator's `defaults`, drops the `unsupported` ones,
+    /// then adds the definition's own derives.
+    fn derive_for(&self, defaults: &[String], unsupported: &[&str], meta: &Meta) -> Result<TokenStream> {
+        let mut names = defaults
+            .iter()
+            .filter(|name| !unsupported.contains(&name.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in &meta.derives {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        self.derive(&names)
+    }
+
+    /// Returns the `#[serde(crate)]` attribute pointing serde derives at the
+    /// runtime crate.
+    fn serde_crate(&self) -> TokenStream {
+        let runtime = &self.runtime;
+        let path = quote!(#runtime::serde).to_string().replace(' ', "");
+        quote!(#[serde(crate = #path)])
+    }
+
+}
+
+/// Returns the `#[cfg]` attribute gating an item on `feature`, if any.
+fn cfg(feature: &Option<String>) -> TokenStream {
+    match *feature {
+        Some(ref feature) => quote!(#[cfg(feature = #feature)]),
+        None => quote!(),
+    }
+}
+
+/// Generates the items for a single schema definition.
+pub fn generate(definition: &Definition, options: &Options) -> Result<Generated> {
+    let cx = Context::new(options)?;
+    let meta = definition.meta();
+    let mut json = TokenStream::new();
+    let mut wrapper = TokenStream::new();
+    if let Some(ref include) = meta.include {
+        let source = fs::read_to_string(include)?;
+        let tokens: TokenStream = source.parse().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", include.display(), err))
+        })?;
+        wrapper.extend(tokens);
+    }
+    match *definition {
+        Definition::Struct(ref def) => {
+            json.extend(json_struct(def, &cx)?);
+            wrapper.extend(struct_wrapper(def, &cx));
+            wrapper.extend(struct_editor(def, &cx));
+            wrapper.extend(struct_builder(def, &cx));
+        },
+        Definition::Enum(ref def) => match def.repr {
+            Repr::String => json.extend(string_enum(def, &cx)?),
+            Repr::Integer => json.extend(integer_enum(def, &cx)?),
+            Repr::Pattern => json.extend(pattern_enum(def, &cx)?),
+        },
+        Definition::Root(ref def) => {
+            json.extend(json_struct(&def.struct_def(), &cx)?);
+            json.extend(root_lookups(def, &cx));
+            wrapper.extend(document(def, &cx));
+        },
+    }
+    Ok(Generated { module: meta.module.clone(), json, wrapper })
+}
+
+/// Wraps `items` in nested `pub mod` blocks for the module path `module`.
+pub fn nest(module: Option<&str>, items: TokenStream) -> TokenStream {
+    match module {
+        Some(module) => module.rsplit("::").fold(items, |items, name| {
+            let name = ident(name);
+            quote! {
+                pub mod #name {
+                    #items
+                }
+            }
+        }),
+        None => items,
+    }
+}
+
+/// Formats `items` as the contents of a Rust source file.
+pub fn pretty(items: TokenStream) -> Result<String> {
+    let file: syn::File = syn::parse2(items)?;
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Returns the identifier `name`.
+///
+/// Names are validated when the schema is loaded.
+pub fn ident(name: &str) -> Ident {
+    Ident::new(name, Span::call_site())
+}
+
+/// Returns the crate relative type path `crate::{prefix}::{path}`.
+fn crate_path(prefix: Option<&str>, path: &str) -> TokenStream {
+    let segments = prefix.into_iter().chain(path.split("::")).map(ident);
+    quote!(crate #(:: #segments)*)
+}
+
+/// Returns the JSON type path `crate::json::{path}`.
+fn json_path(path: &str) -> TokenStream {
+    crate_path(Some("json"), path)
+}
+
+/// Returns the wrapper type path `crate::{path}`.
+fn wrapper_path(path: &str) -> TokenStream {
+    crate_path(None, path)
+}
+
+/// Returns a documentation attribute rendered as `/// {docs}`.
+fn doc(docs: &str) -> TokenStream {
+    let docs = format!(" {}", docs);
+    quote!(#[doc = #docs])
+}
+
+/// Returns the Rust type used for `ty` in the JSON structs.
+fn json_type(ty: &Type, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    match *ty {
+        Type::Integer => quote!(u32),
+        Type::Integer64 => quote!(u64),
+        Type::SignedInteger => quote!(i32),
+        Type::Float => quote!(f32),
+        Type::Double => quote!(f64),
+        Type::Number => quote!(#runtime::serde_json::Number),
+        Type::Bool => quote!(bool),
+        Type::String => quote!(String),
+        Type::Any => quote!(Option<#runtime::serde_json::Value>),
+        Type::Index(ref path) => {
+            let path = json_path(path);
+            quote!(#runtime::Index<#path>)
+        },
+        Type::Struct(ref path) => json_path(path),
+        Type::Enum(ref path) => {
+            let path = json_path(path);
+            quote!(#runtime::validation::Checked<#path>)
+        },
+        Type::Array(ref element) => {
+            let element = json_type(element, cx);
+            quote!(Vec<#element>)
+        },
+        Type::FixedSizeArray(ref element, n) => {
+            let element = json_type(element, cx);
+            let n = proc_macro2::Literal::usize_unsuffixed(n);
+            quote!([#element; #n])
+        },
+        Type::Map(ref key, ref value) => {
+            let key = json_type(key, cx);
+            let value = json_type(value, cx);
+            quote!(#runtime::Map<#key, #value>)
+        },
+        Type::Special(ref path) => path.parse().expect("validated type"),
+    }
+}
+
+/// Returns the Rust expression for the literal `value`.
+fn literal(value: &Literal) -> TokenStream {
+    match *value {
+        Literal::Integer(x) => {
+            let x = proc_macro2::Literal::i64_unsuffixed(x);
+            quote!(#x)
+        },
+        Literal::Float(x) => {
+            let x = proc_macro2::Literal::f64_unsuffixed(x);
+            quote!(#x)
+        },
+        Literal::Bool(x) => quote!(#x),
+        Literal::String(ref x) => quote!(#x),
+        Literal::Variant(_) | Literal::Array(_) => unreachable!("literal requires a field type"),
+    }
+}
+
+/// Returns the Rust expression for the default `value` of a field of type `ty`.
+fn default_value(ty: &Type, value: &Literal, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    match (ty, value) {
+        (Type::Array(ref element), Literal::Array(ref values)) => {
+            let values = values.iter().map(|value| default_value(element, value, cx));
+            quote!(vec![#(#values),*])
+        },
+        (Type::FixedSizeArray(ref element, _), Literal::Array(ref values)) => {
+            let values = values.iter().map(|value| default_value(element, value, cx));
+            quote!([#(#values),*])
+        },
+        (Type::Enum(ref path), Literal::Variant(ref variant)) => {
+            let path = json_path(path);
+            let variant = ident(variant);
+            quote!(#runtime::validation::Checked::Valid(#path::#variant))
+        },
+        (Type::String, Literal::String(ref x)) => quote!(#x.to_string()),
+        _ => literal(value),
+    }
+}
+
+/// Returns the Rust expression comparing `value: &ty` with the default `value`.
+///
+/// Floating point values are compared approximately.
+fn is_default(ty: &Type, default: &Literal, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    match (ty, default) {
+        (Type::Float, _) => {
+            let default = literal(default);
+            quote!(#runtime::approx_eq!(*value, #default))
+        },
+        (Type::Double, _) => {
+            let default = literal(default);
+            quote!(#runtime::float::approx_eq_f64(*value, #default))
+        },
+        (Type::Bool, Literal::Bool(true)) => quote!(*value),
+        (Type::Bool, _) => quote!(!*value),
+        (Type::Enum(ref path), Literal::Variant(ref variant)) => {
+            let path = json_path(path);
+            let variant = ident(variant);
+            quote!(matches!(*value, #runtime::validation::Checked::Valid(#path::#variant)))
+        },
+        (Type::Array(_), Literal::Array(ref values)) if values.is_empty() => quote!(value.is_empty()),
+        (Type::Array(ref element), Literal::Array(ref values)) | (Type::FixedSizeArray(ref element, _), Literal::Array(ref values))
+            if matches!(**element, Type::Array(_) | Type::FixedSizeArray(..)) =>
+        {
+            // Nested arrays are compared item by item, which may hold floats.
+            let n = proc_macro2::Literal::usize_unsuffixed(values.len());
+            let checks = values.iter().enumerate().map(|(index, default)| {
+                let check = is_default(element, default, cx);
+                let index = proc_macro2::Literal::usize_unsuffixed(index);
+                quote!({ let value = &value[#index]; #check })
+            });
+            quote!(value.len() == #n #(&& #checks)*)
+        },
+        (Type::Array(ref element), Literal::Array(ref values)) | (Type::FixedSizeArray(ref element, _), Literal::Array(ref values)) => {
+            let values = values.iter().map(|value| default_value(element, value, cx)).collect::<Vec<_>>();
+            let eq = match **element {
+                Type::Float => quote! {
+                    value.iter().zip(&[#(#values),*]).all(|(a, b)| #runtime::approx_eq!(*a, *b))
+                },
+                Type::Double => quote! {
+                    value.iter().zip(&[#(#values),*]).all(|(a, b)| #runtime::float::approx_eq_f64(*a, *b))
+                },
+                _ => quote!(*value == [#(#values),*]),
+            };
+            match (ty, &**element) {
+                (Type::Array(_), Type::Float | Type::Double) => {
+                    let n = proc_macro2::Literal::usize_unsuffixed(values.len());
+                    quote!(value.len() == #n && #eq)
+                },
+                _ => eq,
+            }
+        },
+        _ => {
+            let default = literal(default);
+            quote!(*value == #default)
+        },
+    }
+}
+
+fn string_enum(def: &EnumDef, cx: &Context) -> Result<TokenStream> {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let docs = doc(&def.meta.docs);
+    let derive = cx.derive_for(&cx.options.enum_derives, &[], &def.meta)?;
+    let variants = def.variants.iter().map(|variant| ident(&variant.ident)).collect::<Vec<_>>();
+    let variant_docs = def.variants.iter().map(|variant| doc(&variant.docs));
+    let values = def.variants.iter().map(|variant| literal(&variant.value)).collect::<Vec<_>>();
+    Ok(quote! {
+        #docs
+        #derive
+        pub enum #name {
+            #(
+                #variant_docs
+                #variants,
+            )*
+        }
+
+        impl #runtime::validation::Enum for #name {
+            fn from_string(value: &str) -> Option<Self> {
+                match value {
+                    #( #values => Some(#name::#variants), )*
+                    _ => None,
+                }
+            }
+        }
+
+        impl #name {
+            /// Returns the equivalent string value.
+            pub fn as_str(&self) -> &'static str {
+                match *self {
+                    #( #name::#variants => #values, )*
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for #name {
+            type Err = #runtime::validation::Unrecognized;
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                <Self as #runtime::validation::Enum>::from_string(value)
+                    .ok_or_else(|| #runtime::validation::Unrecognized::String(value.to_string()))
+            }
+        }
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl #runtime::serde::ser::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: #runtime::serde::ser::Serializer
+            {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+    })
+}
+
+fn integer_enum(def: &EnumDef, cx: &Context) -> Result<TokenStream> {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let docs = doc(&def.meta.docs);
+    let derive = cx.derive_for(&cx.options.enum_derives, &[], &def.meta)?;
+    let variants = def.variants.iter().map(|variant| ident(&variant.ident)).collect::<Vec<_>>();
+    let variant_docs = def.variants.iter().map(|variant| doc(&variant.docs));
+    let values = def.variants.iter().map(|variant| literal(&variant.value)).collect::<Vec<_>>();
+    Ok(quote! {
+        #docs
+        #derive
+        #[repr(u32)]
+        pub enum #name {
+            #(
+                #variant_docs
+                #variants = #values,
+            )*
+        }
+
+        impl #runtime::validation::Enum for #name {
+            fn from_integer(value: u64) -> Option<Self> {
+                match value {
+                    #( #values => Some(#name::#variants), )*
+                    _ => None,
+                }
+            }
+        }
+
+        impl #name {
+            /// Returns the equivalent GLenum value.
+            pub fn as_gl_enum(&self) -> u32 {
+                match *self {
+                    #( #name::#variants => #values, )*
+                }
+            }
+        }
+
+        impl ::std::convert::TryFrom<u32> for #name {
+            type Error = #runtime::validation::Unrecognized;
+            fn try_from(value: u32) -> Result<Self, Self::Error> {
+                <Self as #runtime::validation::Enum>::from_integer(value.into())
+                    .ok_or(#runtime::validation::Unrecognized::Integer(value.into()))
+            }
+        }
+
+        /// Parses the decimal GLenum value, as written in JSON, rather than
+        /// the variant name.
+        impl ::std::str::FromStr for #name {
+            type Err = #runtime::validation::Unrecognized;
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                value
+                    .parse()
+                    .ok()
+                    .and_then(<Self as #runtime::validation::Enum>::from_integer)
+                    .ok_or_else(|| #runtime::validation::Unrecognized::String(value.to_string()))
+            }
+        }
+
+        /// Writes the decimal GLenum value, as written in JSON, rather than
+        /// the variant name.
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "{}", self.as_gl_enum())
+            }
+        }
+
+        impl #runtime::serde::ser::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: #runtime::serde::ser::Serializer
+            {
+                serializer.serialize_u32(self.as_gl_enum())
+            }
+        }
+    })
+}
+
+fn pattern_enum(def: &EnumDef, cx: &Context) -> Result<TokenStream> {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let docs = doc(&def.meta.docs);
+    let has_string = def.variants.iter().any(|variant| variant.payload == Some(Payload::String));
+    let unsupported: &[&str] = if has_string { &["Copy"] } else { &[] };
+    let derive = cx.derive_for(&cx.options.enum_derives, unsupported, &def.meta)?;
+    let mut variants = vec![];
+    let mut literals = vec![];
+    let mut patterns = vec![];
+    let mut as_str = vec![];
+    let mut display = vec![];
+    for variant in &def.variants {
+        let variant_name = ident(&variant.ident);
+        let variant_docs = doc(&variant.docs);
+        let value = literal(&variant.value);
+        match variant.payload {
+            None => {
+                variants.push(quote!(#variant_docs #variant_name,));
+                literals.push(quote!(#value => return Some(#name::#variant_name),));
+                as_str.push(quote!(#name::#variant_name => ::std::borrow::Cow::Borrowed(#value),));
+                display.push(quote!(#name::#variant_name => f.write_str(#value),));
+            },
+            Some(Payload::Integer) => {
+                variants.push(quote!(#variant_docs #variant_name(u32),));
+                // Only the canonical form round-trips, e.g. not `COLOR_01`.
+                patterns.push(quote! {
+                    if let Some(rest) = value.strip_prefix(#value) {
+                        return rest
+                            .parse()
+                            .ok()
+                            .filter(|n: &u32| n.to_string() == rest)
+                            .map(#name::#variant_name);
+                    }
+                });
+                let format = format!("{}{{}}", prefix(variant));
+                display.push(quote!(#name::#variant_name(n) => write!(f, #format, n),));
+            },
+            Some(Payload::String) => {
+                variants.push(quote!(#variant_docs #variant_name(String),));
+                patterns.push(quote! {
+                    if let Some(rest) = value.strip_prefix(#value) {
+                        return Some(#name::#variant_name(rest.to_string()));
+                    }
+                });
+                let format = format!("{}{{}}", prefix(variant));
+                display.push(quote!(#name::#variant_name(ref s) => write!(f, #format, s),));
+            },
+        }
+    }
+    let as_string = if patterns.is_empty() {
+        quote!()
+    } else {
+        quote!(_ => ::std::borrow::Cow::Owned(self.to_string()),)
+    };
+    let literals = if literals.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            match value {
+                #(#literals)*
+                _ => {},
+            }
+        }
+    };
+    Ok(quote! {
+        #docs
+        #derive
+        pub enum #name {
+            #(#variants)*
+        }
+
+        impl #runtime::validation::Enum for #name {
+            fn from_string(value: &str) -> Option<Self> {
+                #literals
+                #(#patterns)*
+                None
+            }
+        }
+
+        impl #name {
+            /// Returns the equivalent string value.
+            pub fn as_str(&self) -> ::std::borrow::Cow<'static, str> {
+                match *self {
+                    #(#as_str)*
+                    #as_string
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for #name {
+            type Err = #runtime::validation::Unrecognized;
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                <Self as #runtime::validation::Enum>::from_string(value)
+                    .ok_or_else(|| #runtime::validation::Unrecognized::String(value.to_string()))
+            }
+        }
+
+        impl ::std::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match *self {
+                    #(#display)*
+                }
+            }
+        }
+
+        impl #runtime::serde::ser::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: #runtime::serde::ser::Serializer
+            {
+                serializer.collect_str(self)
+            }
+        }
+    })
+}
+
+/// Returns the prefix of a pattern variant.
+fn prefix(variant: &VariantDef) -> &str {
+    match variant.value {
+        Literal::String(ref prefix) => prefix,
+        _ => unreachable!("pattern variants have string values"),
+    }
+}
+
+fn json_struct(def: &StructDef, cx: &Context) -> Result<TokenStream> {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let docs = doc(&def.meta.docs);
+    let unsupported: &[&str] = if def.hashable { &[] } else { &["Eq", "Hash"] };
+    let derive = cx.derive_for(&cx.options.struct_derives, unsupported, &def.meta)?;
+    let serde_crate = cx.serde_crate();
+    let names_cfg = cfg(&cx.options.names_feature);
+    let extras_cfg = cfg(&cx.options.extras_feature);
+    let mut fields = vec![];
+    let mut defaults = vec![];
+    let mut initializers = vec![];
+    for field in &def.fields {
+        let field_name = ident(&field.ident);
+        let field_docs = doc(&field.docs);
+        let mut ty = json_type(&field.ty, cx);
+        let mut attrs = vec![];
+        if field.json != field.ident {
+            let json = &field.json;
+            attrs.push(quote!(#[serde(rename = #json)]));
+        }
+        if field.optional {
+            attrs.push(quote!(#[serde(default, skip_serializing_if = "Option::is_none")]));
+            ty = quote!(Option<#ty>);
+        } else if let Some(ref value) = field.default {
+            let default_fn = ident(&format!("{}_default", field.ident));
+            let is_default_fn = ident(&format!("{}_is_default", field.ident));
+            let default_path = format!("{}::{}", def.meta.ident, default_fn);
+            let is_default_path = format!("{}::{}", def.meta.ident, is_default_fn);
+            attrs.push(quote!(#[serde(default = #default_path, skip_serializing_if = #is_default_path)]));
+            let is_default = is_default(&field.ty, value, cx);
+            let value = default_value(&field.ty, value, cx);
+            defaults.push(quote! {
+                pub(crate) fn #default_fn() -> #ty {
+                    #value
+                }
+                fn #is_default_fn(value: &#ty) -> bool {
+                    #is_default
+                }
+            });
+        } else if let Type::Array(_) = field.ty {
+            attrs.push(quote!(#[serde(default, skip_serializing_if = "Vec::is_empty")]));
+        } else if let Type::Map(..) = field.ty {
+            let is_empty = quote!(#runtime::Map::is_empty).to_string().replace(' ', "");
+            attrs.push(quote!(#[serde(default, skip_serializing_if = #is_empty)]));
+        }
+        let initializer = if field.optional {
+            quote!(None)
+        } else if field.default.is_some() {
+            let default_fn = ident(&format!("{}_default", field.ident));
+            quote!(#name::#default_fn())
+        } else {
+            quote!(Default::default())
+        };
+        initializers.push(quote!(#field_name: #initializer,));
+        fields.push(quote! {
+            #field_docs
+            #(#attrs)*
+            pub #field_name: #ty,
+        });
+    }
+    let impl_validate = validate(def, cx);
+    let union = match def.union {
+        Some(ref union) => {
+            let field_name = ident(&union.ident);
+            let field_docs = doc(&union.docs);
+            let ty = ident(&union.enum_ident());
+            fields.push(quote! {
+                #field_docs
+                #[serde(flatten)]
+                pub #field_name: #ty,
+            });
+            json_union(def, union, cx)?
+        },
+        None => quote!(),
+    };
+    let impl_defaults = if defaults.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            impl #name {
+                #(#defaults)*
+            }
+        }
+    };
+    let derives_default = cx.options.struct_derives.iter().chain(&def.meta.derives).any(|name| name == "Default");
+    let impl_default = if def.defaultable && !derives_default {
+        quote! {
+            impl Default for #name {
+                fn default() -> Self {
+                    #name {
+                        #names_cfg
+                        name: None,
+                        #extras_cfg
+                        extras: None,
+                        extensions: None,
+                        #(#initializers)*
+                    }
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+    Ok(quote! {
+        #docs
+        #derive
+        #serde_crate
+        pub struct #name {
+            /// Optional user-defined name for this object.
+            #names_cfg
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub name: Option<String>,
+
+            /// Optional application specific data.
+            #extras_cfg
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub extras: Option<#runtime::serde_json::Value>,
+
+            /// Extension specific data.
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub extensions: Option<#runtime::serde_json::Value>,
+
+            #(#fields)*
+        }
+
+        #impl_defaults
+
+        #impl_default
+
+        #impl_validate
+
+        #union
+    })
+}
+
+/// Returns the `Validate` impl of a JSON struct.
+///
+/// Every struct implements it, so that the checks reach the fields of
+/// nested objects and union payloads.
+fn validate(def: &StructDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let mut checks = def.fields.iter().filter_map(|field| validate_field(field, cx)).collect::<Vec<_>>();
+    if let Some(ref union) = def.union {
+        let field_name = ident(&union.ident);
+        let union_name = ident(&union.enum_ident());
+        let variants = union.variants.iter().map(|variant| ident(&variant.ident));
+        let values = union.variants.iter().map(|variant| &variant.value);
+        checks.push(quote! {
+            match self.#field_name {
+                #(
+                    #union_name::#variants(ref payload) => {
+                        #runtime::validation::Validate::validate(payload, &path.field(#values), &mut *report);
+                    },
+                )*
+            }
+        });
+    }
+    let (path, report) = if checks.is_empty() {
+        (ident("_path"), ident("_report"))
+    } else {
+        (ident("path"), ident("report"))
+    };
+    quote! {
+        impl #runtime::validation::Validate for #name {
+            fn validate<R>(&self, #path: &#runtime::validation::Path, #report: &mut R)
+                where R: FnMut(&#runtime::validation::Path, #runtime::validation::Violation)
+            {
+                #(#checks)*
+            }
+        }
+    }
+}
+
+/// Returns the checks of the constraints on `field` and of the objects it
+/// holds, if any.
+fn validate_field(field: &FieldDef, cx: &Context) -> Option<TokenStream> {
+    let runtime = &cx.runtime;
+    let field_name = ident(&field.ident);
+    let json = &field.json;
+    let constraints = &field.constraints;
+    let is_array = matches!(field.ty, Type::Array(_) | Type::FixedSizeArray(..));
+    // Checks on optional arrays apply to the `Some` value.
+    let items = if field.optional { quote!(items) } else { quote!(self.#field_name) };
+    let mut checks = vec![];
+    if constraints.min_items.is_some() || constraints.max_items.is_some() {
+        let bound = |bound: Option<usize>| match bound {
+            Some(bound) => quote!(Some(#bound)),
+            None => quote!(None),
+        };
+        let min_items = bound(constraints.min_items);
+        let max_items = bound(constraints.max_items);
+        checks.push(quote! {
+            let length = #runtime::validation::Length { min_items: #min_items, max_items: #max_items };
+            if let Some(violation) = length.check(#items.len()) {
+                report(&path, violation);
+            }
+        });
+    }
+    if constraints.unique_items {
+        checks.push(quote! {
+            for index in #runtime::validation::duplicates(&#items) {
+                report(&path.index(index), #runtime::validation::Violation::UniqueItems);
+            }
+        });
+    }
+    if let Some(range) = range(constraints, cx) {
+        checks.push(quote!(let range = #range;));
+        let value = to_f64(field.ty.element(), quote!(value));
+        checks.push(match field.ty {
+            Type::Array(_) | Type::FixedSizeArray(..) => quote! {
+                for (index, &value) in #items.iter().enumerate() {
+                    if let Some(violation) = range.check(#value) {
+                        report(&path.index(index), violation);
+                    }
+                }
+            },
+            _ if field.optional => quote! {
+                if let Some(value) = self.#field_name {
+                    if let Some(violation) = range.check(#value) {
+                        report(&path, violation);
+                    }
+                }
+            },
+            _ => quote! {
+                let value = self.#field_name;
+                if let Some(violation) = range.check(#value) {
+                    report(&path, violation);
+                }
+            },
+        });
+    }
+    if field.optional && is_array {
+        checks.extend(validate_nested(&field.ty, quote!(items), cx));
+    } else if field.optional {
+        if let Some(nested) = validate_nested(&field.ty, quote!(value), cx) {
+            checks.push(quote! {
+                if let Some(ref value) = self.#field_name {
+                    #nested
+                }
+            });
+        }
+    } else if is_array {
+        checks.extend(validate_nested(&field.ty, quote!(self.#field_name), cx));
+    } else {
+        checks.extend(validate_nested(&field.ty, quote!(&self.#field_name), cx));
+    }
+    if checks.is_empty() {
+        None
+    } else if field.optional && is_array {
+        Some(quote! {
+            if let Some(ref items) = self.#field_name {
+                let path = path.field(#json);
+                #(#checks)*
+            }
+        })
+    } else {
+        Some(quote! {
+            {
+                let path = path.field(#json);
+                #(#checks)*
+            }
+        })
+    }
+}
+
+/// Returns the statements validating the structs within `value` of type `ty`,
+/// if there are any.
+///
+/// `value` is a reference, or an array place, and `path` its path.
+fn validate_nested(ty: &Type, value: TokenStream, cx: &Context) -> Option<TokenStream> {
+    let runtime = &cx.runtime;
+    match *ty {
+        Type::Struct(_) => Some(quote! {
+            #runtime::validation::Validate::validate(#value, &path, &mut *report);
+        }),
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+            let nested = validate_nested(element, quote!(value), cx)?;
+            Some(quote! {
+                for (index, value) in #value.iter().enumerate() {
+                    let path = path.index(index);
+                    #nested
+                }
+            })
+        },
+        _ => None,
+    }
+}
+
+/// Returns the expression converting `value` of the numeric type `ty` to
+/// `f64`, which is exact for every type but `u64`.
+fn to_f64(ty: &Type, value: TokenStream) -> TokenStream {
+    match *ty {
+        Type::Integer64 => quote!(#value as f64),
+        _ => quote!(f64::from(#value)),
+    }
+}
+
+/// Returns the `Range` checking the numeric bounds of `constraints`, if any.
+fn range(constraints: &Constraints, cx: &Context) -> Option<TokenStream> {
+    let runtime = &cx.runtime;
+    let bound = |bound: &Option<Literal>| {
+        let x = match *bound {
+            Some(Literal::Integer(x)) => proc_macro2::Literal::f64_unsuffixed(x as f64),
+            Some(Literal::Float(x)) => proc_macro2::Literal::f64_unsuffixed(x),
+            _ => return quote!(None),
+        };
+        quote!(Some(#x))
+    };
+    if constraints.minimum.is_none() && constraints.exclusive_minimum.is_none() && constraints.maximum.is_none() {
+        return None;
+    }
+    let minimum = bound(&constraints.minimum);
+    let exclusive_minimum = bound(&constraints.exclusive_minimum);
+    let maximum = bound(&constraints.maximum);
+    Some(quote! {
+        #runtime::validation::Range {
+            minimum: #minimum,
+            exclusive_minimum: #exclusive_minimum,
+            maximum: #maximum,
+        }
+    })
+}
+
+/// Returns the enumeration holding the payload of a union definition.
+///
+/// In JSON data, the payload is split into a discriminator and a property
+/// named after it, so both serde impls are written by hand. Deserialization
+/// fails unless exactly the payload named by the discriminator is present.
+fn json_union(def: &StructDef, union: &UnionDef, cx: &Context) -> Result<TokenStream> {
+    let runtime = &cx.runtime;
+    let name = ident(&union.enum_ident());
+    let docs = doc(&union.docs);
+    let mut unsupported = vec!["Default", "Deserialize", "Serialize"];
+    if !def.hashable {
+        unsupported.extend(["Eq", "Hash"]);
+    }
+    let derive = cx.derive_for(&cx.options.struct_derives, &unsupported, &def.meta)?;
+    let tag = &union.tag;
+    let variants = union.variants.iter().map(|variant| ident(&variant.ident)).collect::<Vec<_>>();
+    let variant_docs = union.variants.iter().map(|variant| doc(&variant.docs));
+    let values = union.variants.iter().map(|variant| &variant.value).collect::<Vec<_>>();
+    let payloads = union.variants.iter().map(|variant| json_path(&variant.path));
+    let expecting = format!("an object with a `{}` property", tag);
+    Ok(quote! {
+        #docs
+        #derive
+        pub enum #name {
+            #(
+                #variant_docs
+                #variants(#payloads),
+            )*
+        }
+
+        impl #runtime::serde::Serialize for #name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: #runtime::serde::Serializer
+            {
+                use #runtime::serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(2))?;
+                match *self {
+                    #(
+                        #name::#variants(ref payload) => {
+                            map.serialize_entry(#tag, #values)?;
+                            map.serialize_entry(#values, payload)?;
+                        },
+                    )*
+                }
+                map.end()
+            }
+        }
+
+        impl<'de> #runtime::serde::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where D: #runtime::serde::Deserializer<'de>
+            {
+                use #runtime::serde::de;
+                const VARIANTS: &[&str] = &[#(#values),*];
+                struct Visitor;
+                impl<'de> de::Visitor<'de> for Visitor {
+                    type Value = #name;
+                    fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                        f.write_str(#expecting)
+                    }
+                    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                        where A: de::MapAccess<'de>
+                    {
+                        let mut tag: Option<String> = None;
+                        let mut payloads = vec![];
+                        while let Some(key) = map.next_key::<String>()? {
+                            if key == #tag {
+                                if tag.is_some() {
+                                    return Err(de::Error::duplicate_field(#tag));
+                                }
+                                tag = Some(map.next_value()?);
+                            } else if VARIANTS.contains(&key.as_str()) {
+                                payloads.push((key, map.next_value::<#runtime::serde_json::Value>()?));
+                            } else {
+                                map.next_value::<de::IgnoredAny>()?;
+                            }
+                        }
+                        let tag = tag.ok_or_else(|| de::Error::missing_field(#tag))?;
+                        if !VARIANTS.contains(&tag.as_str()) {
+                            return Err(de::Error::unknown_variant(&tag, VARIANTS));
+                        }
+                        if let Some((key, _)) = payloads.iter().find(|(key, _)| *key != tag) {
+                            return Err(de::Error::custom(format_args!("unexpected field `{}` for {} `{}`", key, #tag, tag)));
+                        }
+                        let payload = match (payloads.pop(), payloads.is_empty()) {
+                            (Some((_, payload)), true) => payload,
+                            (Some(_), false) => return Err(de::Error::custom(format_args!("duplicate field `{}`", tag))),
+                            (None, _) => return Err(de::Error::custom(format_args!("missing field `{}`", tag))),
+                        };
+                        let result = match tag.as_str() {
+                            #(#values => #runtime::serde_json::from_value(payload).map(#name::#variants),)*
+                            _ => unreachable!(),
+                        };
+                        result.map_err(de::Error::custom)
+                    }
+                }
+                deserializer.deserialize_map(Visitor)
+            }
+        }
+    })
+}
+
+fn struct_wrapper(def: &StructDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let name = ident(&def.meta.ident);
+    let name_ref = ident(&format!("{}Ref", def.meta.ident));
+    let docs = doc(&def.meta.docs);
+    let ref_docs = doc(&format!("Owned counterpart of [`{}`], which may be stored or sent to other threads.", def.meta.ident));
+    let names_cfg = cfg(&cx.options.names_feature);
+    let extras_cfg = cfg(&cx.options.extras_feature);
+    let json = json_path(&def.meta.path());
+    let mut methods = vec![];
+    let mut ref_methods = vec![];
+    let mut extra = vec![];
+    for field in &def.fields {
+        if field.hidden {
+            // Don't expose this field in the wrapper.
+            continue;
+        }
+        let field_name = ident(&field.ident);
+        let field_docs = doc(&field.docs);
+        let optional = field.optional;
+        // Methods returning the same data from both wrappers, given the JSON
+        // object and the lifetime of data borrowed from it.
+        let plain = |method: &dyn Fn(&TokenStream, &TokenStream) -> TokenStream| {
+            (method(&quote!(self.json), &quote!('a)), method(&quote!(self.as_json()), &quote!('_)))
+        };
+        let (method, ref_method) = match field.ty {
+            // Data types that support optional semantics:
+            Type::Index(ref of) if field.scope.is_some() => {
+                let of_ref = handle_path(of);
+                let of = wrapper_path(of);
+                let scope = ident(field.scope.as_ref().unwrap());
+                let lookup = quote! {
+                    self.parent.json.#scope.get(index.value()).map(|json| {
+                        #of::new(self.document, index.value(), json, self.parent.clone())
+                    })
+                };
+                // The document is shared immutably, so the object found once
+                // is found by the handle thereafter.
+                let handle = quote! {{
+                    let index = index.value();
+                    self.parent.as_json().#scope.get(index)?;
+                    let locate = self.parent.locate.clone();
+                    let locate = crate::locate(move |document| &locate(document).#scope[index]);
+                    Some(#of_ref::new(self.document.clone(), index, locate, self.parent.clone()))
+                }};
+                if optional {
+                    (
+                        quote! {
+                            ///
+                            /// `None` if absent, or if the index is out of range.
+                            pub fn #field_name(&self) -> Option<#of<'a>> {
+                                self.json.#field_name.and_then(|index| #lookup)
+                            }
+                        },
+                        quote! {
+                            ///
+                            /// `None` if absent, or if the index is out of range.
+                            pub fn #field_name(&self) -> Option<#of_ref> {
+                                let index = self.as_json().#field_name?;
+                                #handle
+                            }
+                        },
+                    )
+                } else {
+                    (
+                        quote! {
+                            ///
+                            /// `None` if the index is out of range.
+                            pub fn #field_name(&self) -> Option<#of<'a>> {
+                                let index = self.json.#field_name;
+                                #lookup
+                            }
+                        },
+                        quote! {
+                            ///
+                            /// `None` if the index is out of range.
+                            pub fn #field_name(&self) -> Option<#of_ref> {
+                                let index = self.as_json().#field_name;
+                                #handle
+                            }
+                        },
+                    )
+                }
+            },
+            Type::Index(ref of) if optional => {
+                let of_ref = handle_path(of);
+                let of = wrapper_path(of);
+                (
+                    quote! {
+                        ///
+                        /// `None` if absent, or if the index is out of range.
+                        pub fn #field_name(&self) -> Option<#of<'a>> {
+                            self.json.#field_name.and_then(|index| self.document.try_get(index))
+                        }
+                    },
+                    quote! {
+                        ///
+                        /// `None` if absent, or if the index is out of range.
+                        pub fn #field_name(&self) -> Option<#of_ref> {
+                            self.as_json().#field_name.and_then(|index| self.document.try_get_ref(index))
+                        }
+                    },
+                )
+            },
+            Type::Index(ref of) => {
+                let of_ref = handle_path(of);
+                let of = wrapper_path(of);
+                (
+                    quote! {
+                        ///
+                        /// `None` if the index is out of range.
+                        pub fn #field_name(&self) -> Option<#of<'a>> {
+                            self.document.try_get(self.json.#field_name)
+                        }
+                    },
+                    quote! {
+                        ///
+                        /// `None` if the index is out of range.
+                        pub fn #field_name(&self) -> Option<#of_ref> {
+                            self.document.try_get_ref(self.as_json().#field_name)
+                        }
+                    },
+                )
+            },
+            Type::Struct(ref of) if optional => {
+                let of_ref = handle_path(of);
+                let of = wrapper_path(of);
+                (
+                    quote! {
+                        pub fn #field_name(&self) -> Option<#of<'a>> {
+                            self.json.#field_name.as_ref().map(|json| #of::new(self.document, 0, json, self.clone()))
+                        }
+                    },
+                    quote! {
+                        pub fn #field_name(&self) -> Option<#of_ref> {
+                            self.as_json().#field_name.as_ref()?;
+                            let locate = self.locate.clone();
+                            let locate = crate::locate(move |document| locate(document).#field_name.as_ref().unwrap());
+                            Some(#of_ref::new(self.document.clone(), 0, locate, self.clone()))
+                        }
+                    },
+                )
+            },
+            Type::Struct(ref of) => {
+                let of_ref = handle_path(of);
+                let of = wrapper_path(of);
+                (
+                    quote! {
+                        pub fn #field_name(&self) -> #of<'a> {
+                            #of::new(self.document, 0, &self.json.#field_name, self.clone())
+                        }
+                    },
+                    quote! {
+                        pub fn #field_name(&self) -> #of_ref {
+                            let locate = self.locate.clone();
+                            let locate = crate::locate(move |document| &locate(document).#field_name);
+                            #of_ref::new(self.document.clone(), 0, locate, self.clone())
+                        }
+                    },
+                )
+            },
+            Type::FixedSizeArray(ref element, _) if element.is_copy() => {
+                let ty = json_type(&field.ty, cx);
+                let ty = if optional { quote!(Option<#ty>) } else { ty };
+                plain(&|json, _| {
+                    quote! {
+                        pub fn #field_name(&self) -> #ty {
+                            #json.#field_name
+                        }
+                    }
+                })
+            },
+            Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+                let iter = ident(&field.ident.to_pascal_case());
+                // Optional arrays are iterated through the `Some` value.
+                let items = |json: &TokenStream| if optional { quote!(items) } else { quote!(#json.#field_name) };
+                // Owned handles are made on demand from the array of their
+                // parent, which is located afresh.
+                let (len, located) = if optional {
+                    (
+                        quote!(self.as_json().#field_name.as_ref()?.len()),
+                        quote!(.#field_name.as_ref().unwrap()[index]),
+                    )
+                } else {
+                    (quote!(self.as_json().#field_name.len()), quote!(.#field_name[index]))
+                };
+                let construct = match **element {
+                    Type::Struct(ref of) => {
+                        let json = json_path(of);
+                        let of_ref = handle_path(of);
+                        let of = wrapper_path(of);
+                        extra.push(quote! {
+                            #[derive(Clone, Debug)]
+                            pub struct #iter<'a> {
+                                pub(crate) iter: ::std::iter::Enumerate<::std::slice::Iter<'a, #json>>,
+                                pub(crate) document: &'a crate::Document,
+                                pub(crate) parent: #name<'a>,
+                            }
+
+                            impl<'a> ExactSizeIterator for #iter<'a> {}
+                            impl<'a> Iterator for #iter<'a> {
+                                type Item = #of<'a>;
+                                fn next(&mut self) -> Option<Self::Item> {
+                                    self.iter.next().map(|(index, json)| #of::new(self.document, index, json, self.parent.clone()))
+                                }
+                                fn size_hint(&self) -> (usize, Option<usize>) {
+                                    self.iter.size_hint()
+                                }
+                            }
+                        });
+                        let items = items(&quote!(self.json));
+                        Some((
+                            quote!(#iter<'a>),
+                            quote!(#iter { document: self.document, iter: #items.iter().enumerate(), parent: self.clone() }),
+                            quote!(crate::Handles<#of_ref>),
+                            quote! {
+                                let parent = self.clone();
+                                crate::Handles::new(#len, move |index| {
+                                    let locate = parent.locate.clone();
+                                    let locate = crate::locate(move |document| &locate(document) #located);
+                                    #of_ref::new(parent.document.clone(), index, locate, parent.clone())
+                                })
+                            },
+                        ))
+                    },
+                    Type::Index(ref of) => {
+                        let json = json_path(of);
+                        let of_ref = handle_path(of);
+                        let of = wrapper_path(of);
+                        extra.push(quote! {
+                            /// Visits the objects the indices refer to, with `None` for
+                            /// indices out of range.
+                            #[derive(Clone, Debug)]
+                            pub struct #iter<'a> {
+                                pub(crate) iter: ::std::slice::Iter<'a, #runtime::Index<#json>>,
+                                pub(crate) document: &'a crate::Document,
+                            }
+
+                            impl<'a> ExactSizeIterator for #iter<'a> {}
+                            impl<'a> Iterator for #iter<'a> {
+                                type Item = Option<#of<'a>>;
+                                fn next(&mut self) -> Option<Self::Item> {
+                                    self.iter.next().map(|index| self.document.try_get(*index))
+                                }
+                                fn size_hint(&self) -> (usize, Option<usize>) {
+                                    self.iter.size_hint()
+                                }
+                            }
+                        });
+                        let items = items(&quote!(self.json));
+                        Some((
+                            quote!(#iter<'a>),
+                            quote!(#iter { document: self.document, iter: #items.iter() }),
+                            quote!(crate::Handles<Option<#of_ref>>),
+                            quote! {
+                                let parent = self.clone();
+                                crate::Handles::new(#len, move |index| parent.document.try_get_ref(parent.as_json() #located))
+                            },
+                        ))
+                    },
+                    Type::String => {
+                        extra.push(quote! {
+                            pub type #iter<'a> = ::std::iter::Map<::std::slice::Iter<'a, String>, fn(&'a String) -> &'a str>;
+                        });
+                        None
+                    },
+                    Type::Enum(ref of) => {
+                        let of = json_path(of);
+                        let checked = quote!(#runtime::validation::Checked<#of>);
+                        extra.push(quote! {
+                            pub type #iter<'a> = ::std::iter::Cloned<::std::slice::Iter<'a, #checked>>;
+                        });
+                        None
+                    },
+                    // Copy types, numbers, and nested arrays and maps are
+                    // returned as they are.
+                    _ => {
+                        let element = json_type(element, cx);
+                        extra.push(quote! {
+                            pub type #iter<'a> = ::std::slice::Iter<'a, #element>;
+                        });
+                        None
+                    },
+                };
+                let (ty, construct, ref_ty, ref_construct) = match construct {
+                    Some(construct) => construct,
+                    None => {
+                        let construct = |json: &TokenStream| {
+                            let items = items(json);
+                            match **element {
+                                Type::String => quote!(#items.iter().map(String::as_str)),
+                                Type::Enum(_) => quote!(#items.iter().cloned()),
+                                _ => quote!(#items.iter()),
+                            }
+                        };
+                        let (construct, ref_construct) = (construct(&quote!(self.json)), construct(&quote!(self.as_json())));
+                        (quote!(#iter<'a>), construct, quote!(#iter<'_>), ref_construct)
+                    },
+                };
+                if optional {
+                    let ref_method = match **element {
+                        Type::Struct(_) | Type::Index(_) => quote! {
+                            pub fn #field_name(&self) -> Option<#ref_ty> {
+                                Some({ #ref_construct })
+                            }
+                        },
+                        _ => quote! {
+                            pub fn #field_name(&self) -> Option<#ref_ty> {
+                                self.as_json().#field_name.as_ref().map(|items| #ref_construct)
+                            }
+                        },
+                    };
+                    (
+                        quote! {
+                            pub fn #field_name(&self) -> Option<#ty> {
+                                self.json.#field_name.as_ref().map(|items| #construct)
+                            }
+                        },
+                        ref_method,
+                    )
+                } else {
+                    (
+                        quote! {
+                            pub fn #field_name(&self) -> #ty {
+                                #construct
+                            }
+                        },
+                        quote! {
+                            pub fn #field_name(&self) -> #ref_ty {
+                                #ref_construct
+                            }
+                        },
+                    )
+                }
+            },
+            Type::String if optional => plain(&|json, lt| {
+                quote! {
+                    pub fn #field_name(&self) -> Option<&#lt str> {
+                        #json.#field_name.as_ref().map(|string| string.as_str())
+                    }
+                }
+            }),
+            Type::String => plain(&|json, lt| {
+                quote! {
+                    pub fn #field_name(&self) -> &#lt str {
+                        #json.#field_name.as_str()
+                    }
+                }
+            }),
+            Type::Integer | Type::Integer64 | Type::SignedInteger | Type::Float | Type::Double | Type::Bool => {
+                let ty = json_type(&field.ty, cx);
+                let ty = if optional { quote!(Option<#ty>) } else { ty };
+                plain(&|json, _| {
+                    quote! {
+                        pub fn #field_name(&self) -> #ty {
+                            #json.#field_name
+                        }
+                    }
+                })
+            },
+            Type::Number if optional => plain(&|json, lt| {
+                quote! {
+                    pub fn #field_name(&self) -> Option<&#lt #runtime::serde_json::Number> {
+                        #json.#field_name.as_ref()
+                    }
+                }
+            }),
+            Type::Number => plain(&|json, lt| {
+                quote! {
+                    pub fn #field_name(&self) -> &#lt #runtime::serde_json::Number {
+                        &#json.#field_name
+                    }
+                }
+            }),
+            Type::Enum(ref of) if optional => {
+                let of = json_path(of);
+                plain(&|json, _| {
+                    quote! {
+                        pub fn #field_name(&self) -> Option<#runtime::validation::Checked<#of>> {
+                            #json.#field_name.clone()
+                        }
+                    }
+                })
+            },
+            Type::Enum(ref of) => {
+                let of = json_path(of);
+                plain(&|json, _| {
+                    quote! {
+                        pub fn #field_name(&self) -> #runtime::validation::Checked<#of> {
+                            #json.#field_name.clone()
+                        }
+                    }
+                })
+            },
+            // Data types that don't support optional semantics:
+            Type::Map(ref key, ref value) => {
+                let iter = ident(&field.ident.to_pascal_case());
+                let key_json = json_type(key, cx);
+                let value_json = json_type(value, cx);
+                let (key_ty, key_param, key_wrapper, key_eq) = match **key {
+                    Type::String => (quote!(&'a str), quote!(&str), quote!(key.as_str()), quote!(k == key)),
+                    Type::Enum(ref path) => {
+                        let path = json_path(path);
+                        let key_eq = quote!(matches!(*k, #runtime::validation::Checked::Valid(ref k) if k == key));
+                        (quote!(#runtime::validation::Checked<#path>), quote!(&#path), quote!(key.clone()), key_eq)
+                    },
+                    _ => (quote!(&'a #key_json), quote!(&#key_json), quote!(key), quote!(k == key)),
+                };
+                let (value_ty, value_wrapper) = match **value {
+                    Type::Index(ref of) => {
+                        let of = wrapper_path(of);
+                        (quote!(Option<#of<'a>>), quote!(self.document.try_get(*value)))
+                    },
+                    Type::String => (quote!(&'a str), quote!(value.as_str())),
+                    Type::Enum(ref path) => {
+                        let path = json_path(path);
+                        (quote!(#runtime::validation::Checked<#path>), quote!(value.clone()))
+                    },
+                    _ if value.is_copy() => (value_json.clone(), quote!(*value)),
+                    // Numbers and nested arrays and maps are returned as they are.
+                    _ => (quote!(&'a #value_json), quote!(value)),
+                };
+                // Only indices are looked up in the document.
+                let (document_field, document_init, ref_document_init) = match **value {
+                    Type::Index(_) => (
+                        quote!(pub(crate) document: &'a crate::Document,),
+                        quote!(document: self.document,),
+                        quote!(document: &self.document,),
+                    ),
+                    _ => (quote!(), quote!(), quote!()),
+                };
+                extra.push(quote! {
+                    #[derive(Clone, Debug)]
+                    pub struct #iter<'a> {
+                        pub(crate) map: &'a #runtime::Map<#key_json, #value_json>,
+                        pub(crate) iter: #runtime::map::Iter<'a, #key_json, #value_json>,
+                        #document_field
+                    }
+
+                    impl<'a> #iter<'a> {
+                        /// Returns the value for `key`, if any.
+                        pub fn get(&self, key: #key_param) -> Option<#value_ty> {
+                            self.map.iter().find(|&(k, _)| #key_eq).map(|(_, value)| #value_wrapper)
+                        }
+                    }
+
+                    impl<'a> ExactSizeIterator for #iter<'a> {}
+                    impl<'a> Iterator for #iter<'a> {
+                        type Item = (#key_ty, #value_ty);
+                        fn next(&mut self) -> Option<Self::Item> {
+                            self.iter.next().map(|(key, value)| (#key_wrapper, #value_wrapper))
+                        }
+                        fn size_hint(&self) -> (usize, Option<usize>) {
+                            self.iter.size_hint()
+                        }
+                    }
+                });
+                let method = quote! {
+                    pub fn #field_name(&self) -> #iter<'a> {
+                        #iter { #document_init map: &self.json.#field_name, iter: self.json.#field_name.iter() }
+                    }
+                };
+                // Owned handles of indexed objects are paired with owned keys.
+                let ref_method = match **value {
+                    Type::Index(ref of) => {
+                        let of_ref = handle_path(of);
+                        // The entries are read once, rather than found again by
+                        // position for every handle.
+                        quote! {
+                            pub fn #field_name(&self) -> crate::Handles<(#key_json, Option<#of_ref>)> {
+                                let entries = self
+                                    .as_json()
+                                    .#field_name
+                                    .iter()
+                                    .map(|(key, value)| (key.clone(), *value))
+                                    .collect::<Vec<(#key_json, #value_json)>>();
+                                let document = self.document.clone();
+                                crate::Handles::new(entries.len(), move |index| {
+                                    let (ref key, value) = entries[index];
+                                    (key.clone(), document.try_get_ref(value))
+                                })
+                            }
+                        }
+                    },
+                    _ => quote! {
+                        pub fn #field_name(&self) -> #iter<'_> {
+                            #iter { #ref_document_init map: &self.as_json().#field_name, iter: self.as_json().#field_name.iter() }
+                        }
+                    },
+                };
+                (method, ref_method)
+            },
+            Type::Any => plain(&|json, lt| {
+                quote! {
+                    pub fn #field_name(&self) -> Option<&#lt #runtime::serde_json::Value> {
+                        #json.#field_name.as_ref()
+                    }
+                }
+            }),
+            // Not exposed in the wrapper.
+            Type::Special(_) => continue,
+        };
+        methods.push(quote! {
+            #field_docs
+            #method
+        });
+        ref_methods.push(quote! {
+            #field_docs
+            #ref_method
+        });
+    }
+    if let Some(ref union) = def.union {
+        let field_name = ident(&union.ident);
+        let union_docs = doc(&union.docs);
+        let union_name = ident(&union.enum_ident());
+        let union_ref = ident(&format!("{}Ref", union.enum_ident()));
+        let union_json = json_path(&def.union_path().expect("union definition"));
+        let variants = union.variants.iter().map(|variant| ident(&variant.ident)).collect::<Vec<_>>();
+        let variant_docs = union.variants.iter().map(|variant| doc(&variant.docs)).collect::<Vec<_>>();
+        let payloads = union.variants.iter().map(|variant| wrapper_path(&variant.path)).collect::<Vec<_>>();
+        let payload_refs = union.variants.iter().map(|variant| handle_path(&variant.path)).collect::<Vec<_>>();
+        // The located object always holds the same payload.
+        let unreachable = if union.variants.len() > 1 { quote!(_ => unreachable!(),) } else { quote!() };
+        methods.push(quote! {
+            #union_docs
+            pub fn #field_name(&self) -> #union_name<'a> {
+                match self.json.#field_name {
+                    #(
+                        #union_json::#variants(ref json) => {
+                            #union_name::#variants(#payloads::new(self.document, 0, json, self.clone()))
+                        },
+                    )*
+                }
+            }
+        });
+        ref_methods.push(quote! {
+            #union_docs
+            pub fn #field_name(&self) -> #union_ref {
+                let locate = self.locate.clone();
+                match self.as_json().#field_name {
+                    #(
+                        #union_json::#variants(_) => {
+                            let locate = crate::locate(move |document| match locate(document).#field_name {
+                                #union_json::#variants(ref json) => json,
+                                #unreachable
+                            });
+                            #union_ref::#variants(#payload_refs::new(self.document.clone(), 0, locate, self.clone()))
+                        },
+                    )*
+                }
+            }
+        });
+        extra.push(quote! {
+            #union_docs
+            #[derive(Clone, Debug)]
+            pub enum #union_name<'a> {
+                #(
+                    #variant_docs
+                    #variants(#payloads<'a>),
+                )*
+            }
+
+            #union_docs
+            #[derive(Clone, Debug)]
+            pub enum #union_ref {
+                #(
+                    #variant_docs
+                    #variants(#payload_refs),
+                )*
+            }
+        });
+    }
+    // Objects held by other structs refer back to them. With several
+    // holders, the parent is one of an enumeration of them, and objects of
+    // top-level arrays may have no parent at all.
+    let (parent_field, parent_param, parent_init, parent_method) = match def.parents[..] {
+        [] => (quote!(), quote!(), quote!(), quote!()),
+        ref parents => {
+            let (parent_ty, parent_docs) = match *parents {
+                [ref parent] => (wrapper_path(parent), format!("Returns the `{}` holding this object.", parent)),
+                _ => {
+                    let parent_enum = ident(&format!("{}Parent", def.meta.ident));
+                    let variants = parent_variants(parents);
+                    let paths = parents.iter().map(|parent| wrapper_path(parent)).collect::<Vec<_>>();
+                    let variant_docs = parents.iter().map(|parent| doc(&format!("Held by a `{}`.", parent)));
+                    let enum_docs = doc(&format!("The object holding a `{}`.", def.meta.path()));
+                    let optional_from = def.array.as_ref().map(|_| {
+                        quote! {
+                            #(
+                                impl<'a> From<#paths<'a>> for Option<#parent_enum<'a>> {
+                                    fn from(parent: #paths<'a>) -> Self {
+                                        Some(#parent_enum::#variants(parent))
+                                    }
+                                }
+                            )*
+                        }
+                    });
+                    extra.push(quote! {
+                        #enum_docs
+                        #[derive(Clone, Debug)]
+                        pub enum #parent_enum<'a> {
+                            #(
+                                #variant_docs
+                                #variants(#paths<'a>),
+                            )*
+                        }
+
+                        #(
+                            impl<'a> From<#paths<'a>> for #parent_enum<'a> {
+                                fn from(parent: #paths<'a>) -> Self {
+                                    #parent_enum::#variants(parent)
+                                }
+                            }
+                        )*
+
+                        #optional_from
+                    });
+                    (quote!(#parent_enum), format!("Returns the object holding this `{}`.", def.meta.ident))
+                },
+            };
+            let (parent_ty, parent_docs) = match def.array {
+                Some(_) => (quote!(Option<#parent_ty<'a>>), format!("{}\n\n`None` for objects of the top-level array.", parent_docs)),
+                None => (quote!(#parent_ty<'a>), parent_docs),
+            };
+            let parent_docs = parent_docs.lines().map(doc);
+            (
+                quote!(pub(crate) parent: #parent_ty,),
+                quote!(, parent: impl Into<#parent_ty>),
+                quote!(parent: parent.into(),),
+                quote! {
+                    #(#parent_docs)*
+                    pub fn parent(&self) -> #parent_ty {
+                        self.parent.clone()
+                    }
+                },
+            )
+        },
+    };
+    let (ref_parent_field, ref_parent_param, ref_parent_method) = match def.parents[..] {
+        [] => (quote!(), quote!(), quote!()),
+        ref parents => {
+            let (parent_ty, parent_docs) = match *parents {
+                [ref parent] => (handle_path(parent), format!("Returns the `{}` holding this object.", parent)),
+                _ => {
+                    let parent_enum = ident(&format!("{}ParentRef", def.meta.ident));
+                    let variants = parent_variants(parents);
+                    let paths = parents.iter().map(|parent| handle_path(parent)).collect::<Vec<_>>();
+                    let variant_docs = parents.iter().map(|parent| doc(&format!("Held by a `{}`.", parent)));
+                    let enum_docs = doc(&format!("The object holding a `{}`.", def.meta.path()));
+                    let optional_from = def.array.as_ref().map(|_| {
+                        quote! {
+                            #(
+                                impl From<#paths> for Option<#parent_enum> {
+                                    fn from(parent: #paths) -> Self {
+                                        Some(#parent_enum::#variants(parent))
+                                    }
+                                }
+                            )*
+                        }
+                    });
+                    extra.push(quote! {
+                        #enum_docs
+                        #[derive(Clone, Debug)]
+                        pub enum #parent_enum {
+                            #(
+                                #variant_docs
+                                #variants(#paths),
+                            )*
+                        }
+
+                        #(
+                            impl From<#paths> for #parent_enum {
+                                fn from(parent: #paths) -> Self {
+                                    #parent_enum::#variants(parent)
+                                }
+                            }
+                        )*
+
+                        #optional_from
+                    });
+                    (quote!(#parent_enum), format!("Returns the object holding this `{}`.", def.meta.ident))
+                },
+            };
+            let (parent_ty, parent_docs) = match def.array {
+                Some(_) => (quote!(Option<#parent_ty>), format!("{}\n\n`None` for objects of the top-level array.", parent_docs)),
+                None => (parent_ty, parent_docs),
+            };
+            let parent_docs = parent_docs.lines().map(doc);
+            (
+                quote!(pub(crate) parent: #parent_ty,),
+                quote!(, parent: impl Into<#parent_ty>),
+                quote! {
+                    #(#parent_docs)*
+                    pub fn parent(&self) -> #parent_ty {
+                        self.parent.clone()
+                    }
+                },
+            )
+        },
+    };
+    if def.array.is_some() {
+        let none = if def.parents.is_empty() { quote!() } else { quote!(, None) };
+        let name_mut = ident(&format!("{}Mut", def.meta.ident));
+        extra.push(quote! {
+            impl<'a> crate::Wrap<'a> for #json {
+                type Wrapper = #name<'a>;
+                type WrapperMut = #name_mut<'a>;
+                fn wrap(&'a self, document: &'a crate::Document, index: usize) -> Self::Wrapper {
+                    #name::new(document, index, self #none)
+                }
+                fn wrap_mut(&'a mut self, lengths: crate::Lengths) -> Self::WrapperMut {
+                    #name_mut::new(self, lengths)
+                }
+            }
+
+            impl crate::WrapRef for #json {
+                type WrapperRef = #name_ref;
+                fn wrap_ref(document: ::std::sync::Arc<crate::Document>, index: usize) -> Self::WrapperRef {
+                    let locate = crate::locate(move |document| {
+                        let index = #runtime::Index::new(index as u32);
+                        #runtime::Get::<#json>::get(document.as_json(), index).unwrap()
+                    });
+                    #name_ref::new(document, index, locate #none)
+                }
+            }
+        });
+    }
+    // Only wrappers of objects holding indices or other objects read the
+    // document, but every constructor takes it.
+    let reads_document = def.union.is_some() || def.fields.iter().any(|field| !field.hidden && wraps_objects(&field.ty));
+    let (document_field, document_param, document_init) = if reads_document {
+        (quote!(pub(crate) document: &'a crate::Document,), ident("document"), quote!(document,))
+    } else {
+        (quote!(), ident("_document"), quote!())
+    };
+    // Objects that nothing holds and that are not in a top-level array are
+    // never wrapped.
+    let wrapped = def.array.is_some() || !def.parents.is_empty();
+    let new = if wrapped {
+        quote! {
+            /// Wraps `json`, the object at `index` of its array, or of the
+            /// top-level array.
+            pub(crate) fn new(#document_param: &'a crate::Document, index: usize, json: &'a #json #parent_param) -> Self {
+                #name { #document_init index, json, #parent_init }
+            }
+        }
+    } else {
+        quote!()
+    };
+    let ref_new = if wrapped {
+        quote! {
+            /// Refers to the object at `index` of its array, or of the
+            /// top-level array, which `locate` finds in `document`.
+            pub(crate) fn new(
+                document: ::std::sync::Arc<crate::Document>,
+                index: usize,
+                locate: crate::Locate<#json>
+                #ref_parent_param
+            ) -> Self {
+                #name_ref { document, index, locate, #parent_init }
+            }
+        }
+    } else {
+        quote!()
+    };
+    let ref_debug = format!("{}Ref", def.meta.ident);
+    quote! {
+        #docs
+        #[derive(Clone, Debug)]
+        pub struct #name<'a> {
+            #document_field
+            pub(crate) index: usize,
+            pub(crate) json: &'a #json,
+            #parent_field
+        }
+
+        impl<'a> #name<'a> {
+            #new
+
+            /// Returns the index of this object in its array, or zero for an
+            /// object held directly by its parent.
+            pub fn index(&self) -> usize {
+                self.index
+            }
+
+            #parent_method
+
+            /// Optional user-defined name for this object.
+            #names_cfg
+            pub fn name(&self) -> Option<&str> {
+                self.json.name.as_ref().map(|name| name.as_str())
+            }
+
+            /// Optional application specific data.
+            #extras_cfg
+            pub fn extras(&self) -> Option<&'a #runtime::serde_json::Value> {
+                self.json.extras.as_ref()
+            }
+
+            /// Extension specific data.
+            pub fn extensions(&self) -> Option<&'a #runtime::serde_json::Value> {
+                self.json.extensions.as_ref()
+            }
+
+            #(#methods)*
+        }
+
+        #ref_docs
+        #[derive(Clone)]
+        pub struct #name_ref {
+            pub(crate) document: ::std::sync::Arc<crate::Document>,
+            pub(crate) index: usize,
+            pub(crate) locate: crate::Locate<#json>,
+            #ref_parent_field
+        }
+
+        impl #name_ref {
+            #ref_new
+
+            /// Returns the JSON object.
+            pub fn as_json(&self) -> &#json {
+                (self.locate)(&self.document)
+            }
+
+            /// Returns the index of this object in its array, or zero for an
+            /// object held directly by its parent.
+            pub fn index(&self) -> usize {
+                self.index
+            }
+
+            #ref_parent_method
+
+            /// Optional user-defined name for this object.
+            #names_cfg
+            pub fn name(&self) -> Option<&str> {
+                self.as_json().name.as_ref().map(|name| name.as_str())
+            }
+
+            /// Optional application specific data.
+            #extras_cfg
+            pub fn extras(&self) -> Option<&#runtime::serde_json::Value> {
+                self.as_json().extras.as_ref()
+            }
+
+            /// Extension specific data.
+            pub fn extensions(&self) -> Option<&#runtime::serde_json::Value> {
+                self.as_json().extensions.as_ref()
+            }
+
+            #(#ref_methods)*
+        }
+
+        impl ::std::fmt::Debug for #name_ref {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(#ref_debug).field("index", &self.index).field("json", self.as_json()).finish()
+            }
+        }
+
+        #(#extra)*
+    }
+}
+
+/// Returns `true` if the wrapper method of a field of type `ty` wraps the
+/// objects it refers to or holds, which reads the document.
+fn wraps_objects(ty: &Type) -> bool {
+    match *ty {
+        Type::Index(_) | Type::Struct(_) => true,
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+            matches!(**element, Type::Index(_) | Type::Struct(_))
+        },
+        Type::Map(_, ref value) => matches!(**value, Type::Index(_)),
+        _ => false,
+    }
+}
+
+/// Returns the owned handle type path `crate::{path}Ref`.
+fn handle_path(path: &str) -> TokenStream {
+    crate_path(None, &format!("{}Ref", path))
+}
+
+/// Returns the identifiers of the variants of a parent enumeration, one per
+/// parent type path.
+///
+/// These are the identifiers of the parents, qualified by module where two
+/// of them are the same.
+fn parent_variants(parents: &[String]) -> Vec<Ident> {
+    let last = |path: &str| path.rsplit("::").next().unwrap_or_default().to_string();
+    parents
+        .iter()
+        .map(|parent| {
+            let clashes = parents.iter().filter(|other| last(other) == last(parent)).count() > 1;
+            if clashes {
+                ident(&parent.replace("::", "_").to_pascal_case())
+            } else {
+                ident(&last(parent))
+            }
+        })
+        .collect()
+}
+
+/// Returns the editing wrapper of a struct definition, e.g. `PrimitiveMut`.
+fn struct_editor(def: &StructDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let name_mut = ident(&format!("{}Mut", def.meta.ident));
+    let docs = doc(&format!("Editing counterpart of [`{}`].", def.meta.ident));
+    let names_cfg = cfg(&cx.options.names_feature);
+    let extras_cfg = cfg(&cx.options.extras_feature);
+    let json = json_path(&def.meta.path());
+    let mut methods = field_editors(&def.fields, &def.scopes, &quote!(self.lengths), cx);
+    let mut extra = vec![];
+    // Indices into arrays of the parent are checked against the lengths it
+    // passes in.
+    let mut scopes = def
+        .fields
+        .iter()
+        .filter(|field| !field.hidden)
+        .filter_map(|field| field.scope.as_ref())
+        .collect::<Vec<_>>();
+    scopes.sort();
+    scopes.dedup();
+    let scope_lengths = scopes.iter().map(|scope| ident(&format!("{}_len", scope))).collect::<Vec<_>>();
+    if let Some(ref union) = def.union {
+        let field_name = ident(&union.ident);
+        let field_mut = ident(&format!("{}_mut", union.ident));
+        let union_docs = doc(&union.docs);
+        let union_mut = ident(&format!("{}Mut", union.enum_ident()));
+        let union_json = json_path(&def.union_path().expect("union definition"));
+        let variants = union.variants.iter().map(|variant| ident(&variant.ident)).collect::<Vec<_>>();
+        let variant_docs = union.variants.iter().map(|variant| doc(&variant.docs));
+        let payloads = union.variants.iter().map(|variant| editor_path(&variant.path)).collect::<Vec<_>>();
+        let payload_args = union.variants.iter().map(|variant| editor_args(&def.scopes, &variant.path).1);
+        let mut payload_scopes = union
+            .variants
+            .iter()
+            .flat_map(|variant| def.scopes.get(&variant.path).into_iter().flatten())
+            .collect::<Vec<_>>();
+        payload_scopes.sort();
+        payload_scopes.dedup();
+        let payload_lengths = payload_scopes.iter().map(|scope| ident(&format!("{}_len", scope)));
+        let payload_scopes = payload_scopes.iter().map(|scope| ident(scope));
+        let set = ident(&format!("set_{}", union.ident));
+        methods.push(quote! {
+            /// Returns the payload for editing.
+            pub fn #field_mut(&mut self) -> #union_mut<'_> {
+                let lengths = self.lengths;
+                #( let #payload_lengths = self.json.#payload_scopes.len(); )*
+                match self.json.#field_name {
+                    #(
+                        #union_json::#variants(ref mut json) => #union_mut::#variants(#payloads::new(json, #payload_args)),
+                    )*
+                }
+            }
+
+            #union_docs
+            ///
+            /// Indices within the payload are not checked.
+            pub fn #set(&mut self, #field_name: #union_json) -> &mut Self {
+                self.json.#field_name = #field_name;
+                self
+            }
+        });
+        extra.push(quote! {
+            #union_docs
+            #[derive(Debug)]
+            pub enum #union_mut<'a> {
+                #(
+                    #variant_docs
+                    #variants(#payloads<'a>),
+                )*
+            }
+        });
+    }
+    let new_docs = if scopes.is_empty() {
+        quote! {
+            /// Wraps `json` for editing, checking indices against `lengths`.
+        }
+    } else {
+        let scopes = scopes.iter().map(|scope| format!("`{}`", scope)).collect::<Vec<_>>().join(", ");
+        let docs = format!(
+            "Wraps `json` for editing, checking indices against `lengths`, and\nindices into the {} of the parent against their lengths.",
+            scopes
+        );
+        let docs = docs.lines().map(doc);
+        quote!(#(#docs)*)
+    };
+    // Only editors of objects holding indices outside the arrays of the
+    // parent, or other objects, check against the lengths, but every
+    // constructor takes them.
+    let checks_lengths = def.union.is_some()
+        || def
+            .fields
+            .iter()
+            .any(|field| !field.hidden && field.scope.is_none() && edits_objects(&field.ty));
+    let (lengths_field, lengths_param, lengths_init) = if checks_lengths {
+        (quote!(pub(crate) lengths: crate::Lengths,), ident("lengths"), quote!(lengths,))
+    } else {
+        (quote!(), ident("_lengths"), quote!())
+    };
+    quote! {
+        #docs
+        #[derive(Debug)]
+        pub struct #name_mut<'a> {
+            pub(crate) json: &'a mut #json,
+            #lengths_field
+            #( pub(crate) #scope_lengths: usize, )*
+        }
+
+        impl<'a> #name_mut<'a> {
+            #new_docs
+            pub(crate) fn new(json: &'a mut #json, #lengths_param: crate::Lengths #(, #scope_lengths: usize)*) -> Self {
+                #name_mut { json, #lengths_init #(#scope_lengths,)* }
+            }
+
+            /// Returns the object being edited.
+            pub fn as_json(&self) -> &#json {
+                self.json
+            }
+
+            /// Optional user-defined name for this object.
+            #names_cfg
+            pub fn set_name(&mut self, name: Option<String>) -> &mut Self {
+                self.json.name = name;
+                self
+            }
+
+            /// Optional application specific data.
+            #extras_cfg
+            pub fn set_extras(&mut self, extras: Option<#runtime::serde_json::Value>) -> &mut Self {
+                self.json.extras = extras;
+                self
+            }
+
+            /// Extension specific data.
+            pub fn set_extensions(&mut self, extensions: Option<#runtime::serde_json::Value>) -> &mut Self {
+                self.json.extensions = extensions;
+                self
+            }
+
+            #(#methods)*
+        }
+
+        #(#extra)*
+    }
+}
+
+/// Returns `true` if the editing methods of a field of type `ty` check
+/// indices, or edit the objects it holds, which takes the document's
+/// `Lengths`.
+fn edits_objects(ty: &Type) -> bool {
+    match *ty {
+        Type::Struct(_) => true,
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) if matches!(**element, Type::Struct(_)) => true,
+        _ => matches!(*ty.element(), Type::Index(_)),
+    }
+}
+
+/// Returns the editing wrapper type path `crate::{path}Mut`.
+fn editor_path(path: &str) -> TokenStream {
+    crate_path(None, &format!("{}Mut", path))
+}
+
+/// Returns the statements reading the lengths of the arrays that the editing
+/// wrapper of the nested object `path` checks indices against, and the
+/// arguments of its constructor.
+///
+/// The statements expect a `lengths` binding of the document's `Lengths`.
+fn editor_args(scopes: &BTreeMap<String, Vec<String>>, path: &str) -> (TokenStream, TokenStream) {
+    let scopes = scopes.get(path).map_or(&[][..], |scopes| &scopes[..]);
+    let lengths = scopes.iter().map(|scope| ident(&format!("{}_len", scope))).collect::<Vec<_>>();
+    let scopes = scopes.iter().map(|scope| ident(scope));
+    (quote!(#( let #lengths = self.json.#scopes.len(); )*), quote!(lengths #(, #lengths)*))
+}
+
+/// Returns the type taken by setters for values of type `ty`.
+///
+/// Enumerations are taken without `Checked`, including in arrays.
+fn setter_type(ty: &Type, cx: &Context) -> TokenStream {
+    match *ty {
+        Type::Enum(ref path) => json_path(path),
+        Type::Array(ref element) => {
+            let element = setter_type(element, cx);
+            quote!(Vec<#element>)
+        },
+        Type::FixedSizeArray(ref element, n) => {
+            let element = setter_type(element, cx);
+            let n = proc_macro2::Literal::usize_unsuffixed(n);
+            quote!([#element; #n])
+        },
+        _ => json_type(ty, cx),
+    }
+}
+
+/// Returns `true` if the setter type for `ty` differs from its JSON type.
+fn has_enum(ty: &Type) -> bool {
+    match *ty {
+        Type::Enum(_) => true,
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => has_enum(element),
+        _ => false,
+    }
+}
+
+/// Returns the expression converting `value` of the setter type for `ty` to
+/// its JSON type.
+fn setter_value(ty: &Type, value: TokenStream, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    match *ty {
+        Type::Enum(_) => quote!(#runtime::validation::Checked::Valid(#value)),
+        Type::Array(ref element) if has_enum(element) => {
+            let item = setter_value(element, quote!(item), cx);
+            quote!(#value.into_iter().map(|item| #item).collect())
+        },
+        Type::FixedSizeArray(ref element, _) if has_enum(element) => {
+            let item = setter_value(element, quote!(item), cx);
+            quote!(#value.map(|item| #item))
+        },
+        _ => value,
+    }
+}
+
+/// Returns the statements checking the indices within `value` of the setter
+/// type for `ty`, if there are any.
+///
+/// Indices are checked against `lengths`, an expression for the document's
+/// `Lengths`, and the first out of range one is returned as an error.
+fn index_checks(ty: &Type, value: TokenStream, lengths: &TokenStream, cx: &Context) -> Option<TokenStream> {
+    let runtime = &cx.runtime;
+    match *ty {
+        Type::Index(ref path) => {
+            let path = json_path(path);
+            Some(quote!(#value.check(#runtime::Count::<#path>::count(&#lengths))?;))
+        },
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+            let nested = index_checks(element, quote!(item), lengths, cx)?;
+            Some(quote! {
+                for item in #value.iter() {
+                    #nested
+                }
+            })
+        },
+        Type::Map(_, ref element) => {
+            let nested = index_checks(element, quote!(value), lengths, cx)?;
+            Some(quote! {
+                for (_, value) in #value.iter() {
+                    #nested
+                }
+            })
+        },
+        _ => None,
+    }
+}
+
+/// Returns the statements checking the indices within `value` of the JSON
+/// type for `ty`, and within the objects it holds, if there may be any.
+///
+/// Indices are checked against a `lengths` binding of the document's
+/// `Lengths`. Nested objects are also given the lengths of the arrays of
+/// `holder` that `scopes`, those of `StructDef::scopes`, name for them.
+fn json_index_checks(
+    ty: &Type,
+    value: TokenStream,
+    holder: &TokenStream,
+    scopes: &BTreeMap<String, Vec<String>>,
+    cx: &Context,
+) -> Option<TokenStream> {
+    match *ty {
+        Type::Struct(ref of) => {
+            let scopes = scopes.get(of).map_or(&[][..], |scopes| &scopes[..]);
+            let scopes = scopes.iter().map(|scope| ident(scope));
+            Some(quote!(#value.check_indices(lengths #(, #holder.#scopes.len())*)?;))
+        },
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+            let nested = json_index_checks(element, quote!(item), holder, scopes, cx)?;
+            Some(quote! {
+                for item in #value.iter() {
+                    #nested
+                }
+            })
+        },
+        Type::Map(_, ref element) => {
+            let nested = json_index_checks(element, quote!(value), holder, scopes, cx)?;
+            Some(quote! {
+                for (_, value) in #value.iter() {
+                    #nested
+                }
+            })
+        },
+        _ => index_checks(ty, value, &quote!(lengths), cx),
+    }
+}
+
+/// Returns the `check_indices` method of a JSON struct, which the
+/// `DocumentBuilder` calls on every object pushed to it.
+fn check_indices(def: &StructDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let json = json_path(&def.meta.path());
+    let mut scopes = def
+        .fields
+        .iter()
+        .filter(|field| !field.hidden)
+        .filter_map(|field| field.scope.as_ref())
+        .collect::<Vec<_>>();
+    scopes.sort();
+    scopes.dedup();
+    let scope_lengths = scopes.iter().map(|scope| ident(&format!("{}_len", scope)));
+    let mut uses_lengths = false;
+    let mut checks = vec![];
+    for field in &def.fields {
+        let field_name = ident(&field.ident);
+        let value = if field.optional { quote!(value) } else { quote!(self.#field_name) };
+        let field_checks = match field.scope {
+            Some(ref scope) if !field.hidden => {
+                let length = ident(&format!("{}_len", scope));
+                Some(quote!(#value.check(#length)?;))
+            },
+            // The lengths of the arrays of the parent are only passed in
+            // for exposed fields.
+            Some(_) => None,
+            None => {
+                let field_checks = json_index_checks(&field.ty, value, &quote!(self), &def.scopes, cx);
+                uses_lengths |= field_checks.is_some();
+                field_checks
+            },
+        };
+        checks.extend(match field_checks {
+            Some(field_checks) if field.optional => Some(quote! {
+                if let Some(ref value) = self.#field_name {
+                    #field_checks
+                }
+            }),
+            field_checks => field_checks,
+        });
+    }
+    if let Some(ref union) = def.union {
+        let field_name = ident(&union.ident);
+        let union_json = json_path(&def.union_path().expect("union definition"));
+        let arms = union.variants.iter().map(|variant| {
+            let variant_name = ident(&variant.ident);
+            let payload_checks = json_index_checks(&Type::Struct(variant.path.clone()), quote!(payload), &quote!(self), &def.scopes, cx);
+            quote! {
+                #union_json::#variant_name(ref payload) => {
+                    #payload_checks
+                },
+            }
+        });
+        uses_lengths = true;
+        checks.push(quote! {
+            match self.#field_name {
+                #(#arms)*
+            }
+        });
+    }
+    let lengths = if uses_lengths { ident("lengths") } else { ident("_lengths") };
+    quote! {
+        impl #json {
+            /// Checks the indices within this object, and the objects it holds,
+            /// against `lengths`, and those into arrays of the parent against
+            /// their lengths, failing on the first out of range one.
+            pub(crate) fn check_indices(
+                &self,
+                #lengths: crate::Lengths
+                #(, #scope_lengths: usize)*
+            ) -> Result<(), #runtime::OutOfRange> {
+                #(#checks)*
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns the setters of `fields`, and the methods editing the objects and
+/// arrays they hold.
+///
+/// `lengths` is an expression for the document's `Lengths`, and `scopes`
+/// those of `StructDef::scopes`.
+fn field_editors(
+    fields: &[FieldDef],
+    scopes: &BTreeMap<String, Vec<String>>,
+    lengths: &TokenStream,
+    cx: &Context,
+) -> Vec<TokenStream> {
+    let runtime = &cx.runtime;
+    let mut methods = vec![];
+    for field in fields {
+        if field.hidden || matches!(field.ty, Type::Special(_)) {
+            continue;
+        }
+        let field_name = ident(&field.ident);
+        let field_docs = doc(&field.docs);
+        let optional = field.optional;
+        let set = ident(&format!("set_{}", field.ident));
+        let ty = setter_type(&field.ty, cx);
+        let param = if optional { quote!(Option<#ty>) } else { ty };
+        let value = if optional && has_enum(&field.ty) {
+            let value = setter_value(&field.ty, quote!(value), cx);
+            quote!(#field_name.map(|value| #value))
+        } else if optional {
+            quote!(#field_name)
+        } else {
+            setter_value(&field.ty, quote!(#field_name), cx)
+        };
+        let checked = if optional { quote!(value) } else { quote!(#field_name) };
+        let checks = match field.scope {
+            Some(ref scope) => {
+                let length = ident(&format!("{}_len", scope));
+                Some(quote!(#checked.check(self.#length)?;))
+            },
+            None => index_checks(&field.ty, checked, lengths, cx),
+        };
+        let checks = match checks {
+            Some(checks) if optional => Some(quote! {
+                if let Some(ref value) = #field_name {
+                    #checks
+                }
+            }),
+            checks => checks,
+        };
+        let holds_structs = matches!(*field.ty.element(), Type::Struct(_));
+        let unchecked = |param: &str| {
+            if holds_structs {
+                let docs = doc(&format!("Indices within `{}` are not checked.", param));
+                quote! {
+                    ///
+                    #docs
+                }
+            } else {
+                quote!()
+            }
+        };
+        let unchecked_field = unchecked(&field.ident);
+        methods.push(match checks {
+            Some(checks) => quote! {
+                #field_docs
+                ///
+                /// Fails if an index is out of range, leaving the field unchanged.
+                pub fn #set(&mut self, #field_name: #param) -> Result<&mut Self, #runtime::OutOfRange> {
+                    #checks
+                    self.json.#field_name = #value;
+                    Ok(self)
+                }
+            },
+            None => quote! {
+                #field_docs
+                #unchecked_field
+                pub fn #set(&mut self, #field_name: #param) -> &mut Self {
+                    self.json.#field_name = #value;
+                    self
+                }
+            },
+        });
+
+        // Nested objects are edited through their own editing wrappers.
+        let field_mut = ident(&format!("{}_mut", field.ident));
+        match field.ty {
+            Type::Struct(ref of) => {
+                let (lets, args) = editor_args(scopes, of);
+                let of = editor_path(of);
+                methods.push(if optional {
+                    quote! {
+                        /// Returns the object for editing, if any.
+                        pub fn #field_mut(&mut self) -> Option<#of<'_>> {
+                            let lengths = #lengths;
+                            #lets
+                            self.json.#field_name.as_mut().map(|json| #of::new(json, #args))
+                        }
+                    }
+                } else {
+                    quote! {
+                        /// Returns the object for editing.
+                        pub fn #field_mut(&mut self) -> #of<'_> {
+                            let lengths = #lengths;
+                            #lets
+                            #of::new(&mut self.json.#field_name, #args)
+                        }
+                    }
+                });
+            },
+            Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => {
+                if let Type::Struct(ref of) = **element {
+                    let (lets, args) = editor_args(scopes, of);
+                    let of = editor_path(of);
+                    let item = if optional {
+                        quote!(self.json.#field_name.as_mut().and_then(|items| items.get_mut(index)))
+                    } else {
+                        quote!(self.json.#field_name.get_mut(index))
+                    };
+                    methods.push(quote! {
+                        /// Returns the object at `index` for editing, if any.
+                        pub fn #field_mut(&mut self, index: usize) -> Option<#of<'_>> {
+                            let lengths = #lengths;
+                            #lets
+                            #item.map(|json| #of::new(json, #args))
+                        }
+                    });
+                }
+            },
+            _ => {},
+        }
+
+        // Only arrays of variable length grow and shrink.
+        let element = match field.ty {
+            Type::Array(ref element) => element,
+            _ => continue,
+        };
+        let push = ident(&format!("push_to_{}", field.ident));
+        let remove = ident(&format!("remove_from_{}", field.ident));
+        let item_ty = setter_type(element, cx);
+        let item_json = json_type(element, cx);
+        let item = setter_value(element, quote!(item), cx);
+        let push_docs = doc(&format!("Appends `item` to `{}`.", field.ident));
+        let unchecked_item = unchecked("item");
+        let items = if optional {
+            quote!(self.json.#field_name.get_or_insert_with(Vec::new))
+        } else {
+            quote!(self.json.#field_name)
+        };
+        methods.push(match index_checks(element, quote!(item), lengths, cx) {
+            Some(checks) => quote! {
+                #push_docs
+                ///
+                /// Fails if an index is out of range, leaving the array unchanged.
+                pub fn #push(&mut self, item: #item_ty) -> Result<&mut Self, #runtime::OutOfRange> {
+                    #checks
+                    #items.push(#item);
+                    Ok(self)
+                }
+            },
+            None => quote! {
+                #push_docs
+                #unchecked_item
+                pub fn #push(&mut self, item: #item_ty) -> &mut Self {
+                    #items.push(#item);
+                    self
+                }
+            },
+        });
+        let remove_docs = doc(&format!("Removes and returns the item of `{}` at `index`, if any.", field.ident));
+        let shifted = if holds_structs {
+            quote! {
+                ///
+                /// Later objects move down by one, and indices referring to
+                /// them are not updated.
+            }
+        } else {
+            quote!()
+        };
+        let items = if optional {
+            quote!(self.json.#field_name.as_mut()?)
+        } else {
+            quote!(self.json.#field_name)
+        };
+        methods.push(quote! {
+            #remove_docs
+            #shifted
+            pub fn #remove(&mut self, index: usize) -> Option<#item_json> {
+                let items = &mut #items;
+                if index < items.len() {
+                    Some(items.remove(index))
+                } else {
+                    None
+                }
+            }
+        });
+    }
+    methods
+}
+
+/// Returns the builder of a JSON struct.
+fn struct_builder(def: &StructDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let name_builder = ident(&format!("{}Builder", def.meta.ident));
+    let docs = doc(&format!("Builds a [`{}`](crate::json::{}), checking that required fields are set.", def.meta.ident, def.meta.path()));
+    let names_cfg = cfg(&cx.options.names_feature);
+    let extras_cfg = cfg(&cx.options.extras_feature);
+    let json = json_path(&def.meta.path());
+    let object = def.meta.path();
+    let FieldBuilders { mut fields, mut setters, mut checks, mut initializers } = field_builders(&object, &def.fields, cx);
+    if let Some(ref union) = def.union {
+        let field_name = ident(&union.ident);
+        let union_docs = doc(&union.docs);
+        let union_json = json_path(&def.union_path().expect("union definition"));
+        let field = &union.ident;
+        fields.push(quote!(#field_name: Option<#union_json>,));
+        setters.push(quote! {
+            #union_docs
+            pub fn #field_name(&mut self, #field_name: #union_json) -> &mut Self {
+                self.#field_name = Some(#field_name);
+                self
+            }
+        });
+        checks.push(quote! {
+            if self.#field_name.is_none() {
+                return Err(#runtime::MissingField { object: #object, field: #field });
+            }
+        });
+        initializers.push(quote!(#field_name: self.#field_name.take().unwrap(),));
+    }
+    let check_indices = check_indices(def, cx);
+    quote! {
+        #check_indices
+
+        #docs
+        #[derive(Debug, Default)]
+        pub struct #name_builder {
+            #names_cfg
+            name: Option<String>,
+            #extras_cfg
+            extras: Option<#runtime::serde_json::Value>,
+            extensions: Option<#runtime::serde_json::Value>,
+            #(#fields)*
+        }
+
+        impl #name_builder {
+            /// Returns a builder with no fields set.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Optional user-defined name for this object.
+            #names_cfg
+            pub fn name(&mut self, name: impl Into<String>) -> &mut Self {
+                self.name = Some(name.into());
+                self
+            }
+
+            /// Optional application specific data.
+            #extras_cfg
+            pub fn extras(&mut self, extras: #runtime::serde_json::Value) -> &mut Self {
+                self.extras = Some(extras);
+                self
+            }
+
+            /// Extension specific data.
+            pub fn extensions(&mut self, extensions: #runtime::serde_json::Value) -> &mut Self {
+                self.extensions = Some(extensions);
+                self
+            }
+
+            #(#setters)*
+
+            /// Builds the object, filling unset fields with their defaults and
+            /// leaving the builder empty.
+            ///
+            /// Fails if a required field is unset, leaving the builder unchanged.
+            pub fn build(&mut self) -> Result<#json, #runtime::MissingField> {
+                #(#checks)*
+                Ok(#json {
+                    #names_cfg
+                    name: self.name.take(),
+                    #extras_cfg
+                    extras: self.extras.take(),
+                    extensions: self.extensions.take(),
+                    #(#initializers)*
+                })
+            }
+        }
+    }
+}
+
+/// The parts of a builder handling the fields of a JSON struct.
+struct FieldBuilders {
+    /// Declarations of the builder fields.
+    fields: Vec<TokenStream>,
+    /// Setters of the builder fields.
+    setters: Vec<TokenStream>,
+    /// Statements returning `MissingField` if a required field is unset.
+    checks: Vec<TokenStream>,
+    /// Initializers of the JSON struct fields, which take the builder fields.
+    initializers: Vec<TokenStream>,
+}
+
+/// Returns the builder parts for `fields` of the JSON struct `object`.
+///
+/// Optional fields and fields with defaults are held as `Option`s, as are
+/// required fields, which are checked. Arrays and maps without defaults are
+/// empty unless set.
+fn field_builders(object: &str, fields: &[FieldDef], cx: &Context) -> FieldBuilders {
+    let runtime = &cx.runtime;
+    let mut builders = FieldBuilders { fields: vec![], setters: vec![], checks: vec![], initializers: vec![] };
+    for field in fields {
+        let field_name = ident(&field.ident);
+        if field.hidden || matches!(field.ty, Type::Special(_)) {
+            builders.initializers.push(quote!(#field_name: Default::default(),));
+            continue;
+        }
+        let field_docs = doc(&field.docs);
+        let ty = json_type(&field.ty, cx);
+        let param = setter_type(&field.ty, cx);
+        let value = setter_value(&field.ty, quote!(#field_name), cx);
+        let (ty, param, value, initializer) = match field.ty {
+            Type::Any => (
+                ty,
+                quote!(#runtime::serde_json::Value),
+                quote!(Some(#field_name)),
+                quote!(self.#field_name.take()),
+            ),
+            _ if field.optional => (quote!(Option<#ty>), param, quote!(Some(#value)), quote!(self.#field_name.take())),
+            _ if field.default.is_some() => {
+                let default_fn = ident(&format!("{}_default", field.ident));
+                let json = json_path(object);
+                (
+                    quote!(Option<#ty>),
+                    param,
+                    quote!(Some(#value)),
+                    quote!(self.#field_name.take().unwrap_or_else(#json::#default_fn)),
+                )
+            },
+            Type::Array(_) | Type::Map(..) => (ty, param, value, quote!(::std::mem::take(&mut self.#field_name))),
+            _ => {
+                let field = &field.ident;
+                builders.checks.push(quote! {
+                    if self.#field_name.is_none() {
+                        return Err(#runtime::MissingField { object: #object, field: #field });
+                    }
+                });
+                (quote!(Option<#ty>), param, quote!(Some(#value)), quote!(self.#field_name.take().unwrap()))
+            },
+        };
+        builders.fields.push(quote!(#field_name: #ty,));
+        builders.setters.push(quote! {
+            #field_docs
+            pub fn #field_name(&mut self, #field_name: #param) -> &mut Self {
+                self.#field_name = #value;
+                self
+            }
+        });
+        builders.initializers.push(quote!(#field_name: #initializer,));
+    }
+    builders
+}
+
+/// Returns the `Get` impls looking up the top-level arrays of the root object.
+fn root_lookups(def: &RootDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let root = ident(&def.meta.ident);
+    let fields = def.collections.iter().map(|collection| ident(&collection.ident));
+    let types = def.collections.iter().map(|collection| json_path(&collection.path));
+    quote! {
+        #(
+            impl #runtime::Get<#types> for #root {
+                fn get(&self, index: #runtime::Index<#types>) -> Option<&#types> {
+                    self.#fields.get(index.value())
+                }
+                fn get_mut(&mut self, index: #runtime::Index<#types>) -> Option<&mut #types> {
+                    self.#fields.get_mut(index.value())
+                }
+            }
+        )*
+    }
+}
+
+/// Returns the `Document` wrapper around the root object.
+fn document(def: &RootDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let docs = doc(&def.meta.docs);
+    let root = json_path(&def.meta.path());
+    let mut methods = vec![];
+    let mut items = vec![];
+    for collection in &def.collections {
+        let field = ident(&collection.ident);
+        let iter = ident(&collection.ident.to_pascal_case());
+        let json = json_path(&collection.path);
+        let wrapper = wrapper_path(&collection.path);
+        let iter_docs = doc(&format!("An `Iterator` that visits every `{}` in the document.", collection.path));
+        let method_docs = doc(&format!("Returns an `Iterator` that visits every `{}` in the document.", collection.path));
+        let push = ident(&format!("push_to_{}", collection.ident));
+        let push_docs = doc(&format!("Appends `json` to the `{}` objects of the document, returning its index.", collection.path));
+        methods.push(quote! {
+            #push_docs
+            ///
+            /// Indices within `json` are not checked.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the array is already too long for an `Index` to refer
+            /// to the new object.
+            pub fn #push(&mut self, json: #json) -> #runtime::Index<#json> {
+                let index = <u32 as ::std::convert::TryFrom<usize>>::try_from(self.json.#field.len())
+                    .expect("too many objects for an index");
+                self.json.#field.push(json);
+                #runtime::Index::new(index)
+            }
+        });
+        let field_ref = ident(&format!("{}_ref", collection.ident));
+        let ref_docs = doc(&format!("Returns an `Iterator` of owned handles to every `{}` in the document.", collection.path));
+        let wrapper_ref = crate_path(None, &format!("{}Ref", collection.path));
+        methods.push(quote! {
+            #ref_docs
+            pub fn #field_ref(self: &::std::sync::Arc<Self>) -> Handles<#wrapper_ref> {
+                let document = self.clone();
+                Handles::new(self.json.#field.len(), move |index| #json::wrap_ref(document.clone(), index))
+            }
+        });
+        methods.push(quote! {
+            #method_docs
+            pub fn #field(&self) -> #iter<'_> {
+                #iter {
+                    document: self,
+                    iter: self.json.#field.iter().enumerate(),
+                }
+            }
+        });
+        items.push(quote! {
+            #iter_docs
+            #[derive(Clone, Debug)]
+            pub struct #iter<'a> {
+                pub(crate) document: &'a Document,
+                pub(crate) iter: ::std::iter::Enumerate<::std::slice::Iter<'a, #json>>,
+            }
+
+            impl<'a> ExactSizeIterator for #iter<'a> {}
+            impl<'a> Iterator for #iter<'a> {
+                type Item = #wrapper<'a>;
+                fn next(&mut self) -> Option<Self::Item> {
+                    let document = self.document;
+                    self.iter.next().map(|(index, json)| json.wrap(document, index))
+                }
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    self.iter.size_hint()
+                }
+            }
+        });
+    }
+    // The `scene` property selects the default scene, if there is one.
+    let default_scene = def.fields.iter().find_map(|field| match field.ty {
+        Type::Index(ref path) if field.ident == "scene" && field.optional => def
+            .collections
+            .iter()
+            .find(|collection| collection.path == *path)
+            .map(|collection| {
+                let scenes = ident(&collection.ident);
+                let wrapper = wrapper_path(path);
+                quote! {
+                    /// Returns the scene to display by default.
+                    ///
+                    /// This is the scene named by the `scene` property if present,
+                    /// otherwise the first scene, if any.
+                    pub fn default_scene(&self) -> Option<#wrapper<'_>> {
+                        match self.json.scene {
+                            Some(index) => self.try_get(index),
+                            None => self.#scenes().next(),
+                        }
+                    }
+                }
+            }),
+        _ => None,
+    });
+    let editors = field_editors(&def.fields, &BTreeMap::new(), &quote!(self.lengths()), cx);
+    let builder = document_builder(def, cx);
+    let fields = def.collections.iter().map(|collection| ident(&collection.ident)).collect::<Vec<_>>();
+    let types = def.collections.iter().map(|collection| json_path(&collection.path));
+    quote! {
+        #docs
+        #[derive(Clone, Debug)]
+        pub struct Document {
+            json: #root,
+        }
+
+        impl Document {
+            /// Wraps the root JSON object.
+            pub fn from_json(json: #root) -> Self {
+                Document { json }
+            }
+
+            /// Returns the root JSON object.
+            pub fn as_json(&self) -> &#root {
+                &self.json
+            }
+
+            /// Returns the root JSON object, consuming the document.
+            pub fn into_json(self) -> #root {
+                self.json
+            }
+
+            /// Returns the object at `index`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `index` is out of range.
+            pub fn get<'a, T>(&'a self, index: #runtime::Index<T>) -> T::Wrapper
+                where T: 'a + Wrap<'a>, #root: #runtime::Get<T>
+            {
+                self.try_get(index).expect("index out of range")
+            }
+
+            /// Returns the object at `index`, or `None` if it is out of range.
+            pub fn try_get<'a, T>(&'a self, index: #runtime::Index<T>) -> Option<T::Wrapper>
+                where T: 'a + Wrap<'a>, #root: #runtime::Get<T>
+            {
+                #runtime::Get::get(&self.json, index).map(|json| json.wrap(self, index.value()))
+            }
+
+            /// Returns the object at `index` for editing.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `index` is out of range.
+            pub fn get_mut<'a, T>(&'a mut self, index: #runtime::Index<T>) -> T::WrapperMut
+                where T: 'a + Wrap<'a>, #root: #runtime::Get<T>
+            {
+                self.try_get_mut(index).expect("index out of range")
+            }
+
+            /// Returns the object at `index` for editing, or `None` if it is out
+            /// of range.
+            pub fn try_get_mut<'a, T>(&'a mut self, index: #runtime::Index<T>) -> Option<T::WrapperMut>
+                where T: 'a + Wrap<'a>, #root: #runtime::Get<T>
+            {
+                let lengths = self.lengths();
+                #runtime::Get::get_mut(&mut self.json, index).map(|json| json.wrap_mut(lengths))
+            }
+
+            /// Returns an owned handle to the object at `index`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `index` is out of range.
+            pub fn get_ref<T>(self: &::std::sync::Arc<Self>, index: #runtime::Index<T>) -> T::WrapperRef
+                where T: WrapRef, #root: #runtime::Get<T>
+            {
+                self.try_get_ref(index).expect("index out of range")
+            }
+
+            /// Returns an owned handle to the object at `index`, or `None` if it
+            /// is out of range.
+            pub fn try_get_ref<T>(self: &::std::sync::Arc<Self>, index: #runtime::Index<T>) -> Option<T::WrapperRef>
+                where T: WrapRef, #root: #runtime::Get<T>
+            {
+                #runtime::Get::get(&self.json, index).map(|_| T::wrap_ref(self.clone(), index.value()))
+            }
+
+            /// Returns the number of objects in each top-level array.
+            fn lengths(&self) -> Lengths {
+                Lengths {
+                    #( #fields: self.json.#fields.len(), )*
+                }
+            }
+
+            #(#methods)*
+
+            #default_scene
+
+            #(#editors)*
+        }
+
+        /// Converts JSON objects into their wrapper types.
+        pub trait Wrap<'a> {
+            /// The wrapper type.
+            type Wrapper;
+
+            /// The editing wrapper type.
+            type WrapperMut;
+
+            /// Wraps `self`, the object at `index` of its top-level array in
+            /// `document`.
+            fn wrap(&'a self, document: &'a Document, index: usize) -> Self::Wrapper;
+
+            /// Wraps `self` for editing, checking indices against `lengths`.
+            fn wrap_mut(&'a mut self, lengths: Lengths) -> Self::WrapperMut;
+        }
+
+        /// Converts indices into owned handles to the objects of a shared
+        /// document.
+        pub trait WrapRef {
+            /// The owned handle type.
+            type WrapperRef;
+
+            /// Refers to the object at `index` of its top-level array in
+            /// `document`.
+            fn wrap_ref(document: ::std::sync::Arc<Document>, index: usize) -> Self::WrapperRef;
+        }
+
+        /// Finds the JSON object of an owned handle in its document.
+        pub(crate) type Locate<T> = ::std::sync::Arc<dyn Fn(&Document) -> &T + Send + Sync>;
+
+        /// Returns the `Locate` function `f`.
+        pub(crate) fn locate<T, F>(f: F) -> Locate<T>
+            where F: Fn(&Document) -> &T + Send + Sync + 'static
+        {
+            ::std::sync::Arc::new(f)
+        }
+
+        /// An `Iterator` of owned handles, made on demand from their indices.
+        #[derive(Clone)]
+        pub struct Handles<T> {
+            range: ::std::ops::Range<usize>,
+            handle: ::std::sync::Arc<dyn Fn(usize) -> T + Send + Sync>,
+        }
+
+        impl<T> Handles<T> {
+            /// Visits the handles `handle` makes for indices below `len`.
+            pub(crate) fn new<F>(len: usize, handle: F) -> Self
+                where F: Fn(usize) -> T + Send + Sync + 'static
+            {
+                Handles { range: 0..len, handle: ::std::sync::Arc::new(handle) }
+            }
+        }
+
+        impl<T> ExactSizeIterator for Handles<T> {}
+        impl<T> Iterator for Handles<T> {
+            type Item = T;
+            fn next(&mut self) -> Option<Self::Item> {
+                self.range.next().map(|index| (self.handle)(index))
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.range.size_hint()
+            }
+        }
+
+        impl<T> ::std::fmt::Debug for Handles<T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct("Handles").field("range", &self.range).finish()
+            }
+        }
+
+        /// The number of objects in each top-level array of a document, which
+        /// the `Index` setters of the editing wrappers check against.
+        #[derive(Clone, Copy, Debug)]
+        pub struct Lengths {
+            #( #fields: usize, )*
+        }
+
+        #(
+            impl #runtime::Count<#types> for Lengths {
+                fn count(&self) -> usize {
+                    self.#fields
+                }
+            }
+        )*
+
+        #(#items)*
+
+        #builder
+    }
+}
+
+/// Returns the `DocumentBuilder`, which builds the root object from its
+/// fields and the objects pushed to its top-level arrays.
+fn document_builder(def: &RootDef, cx: &Context) -> TokenStream {
+    let runtime = &cx.runtime;
+    let names_cfg = cfg(&cx.options.names_feature);
+    let extras_cfg = cfg(&cx.options.extras_feature);
+    let root = json_path(&def.meta.path());
+    let FieldBuilders { mut fields, setters, checks, mut initializers } = field_builders(&def.meta.path(), &def.fields, cx);
+    let mut methods = vec![];
+    for collection in &def.collections {
+        let field = ident(&collection.ident);
+        let json = json_path(&collection.path);
+        let push = ident(&format!("push_to_{}", collection.ident));
+        let push_docs = doc(&format!("Appends `json` to the `{}` objects of the document, returning its index.", collection.path));
+        fields.push(quote!(#field: Vec<#json>,));
+        initializers.push(quote!(#field: ::std::mem::take(&mut self.#field),));
+        methods.push(quote! {
+            #push_docs
+            ///
+            /// Indices within `json` are checked when the document is built.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the index does not fit in a `u32`.
+            pub fn #push(&mut self, json: #json) -> #runtime::Index<#json> {
+                let index = <u32 as ::std::convert::TryFrom<usize>>::try_from(self.#field.len()).expect("too many objects for an index");
+                self.#field.push(json);
+                #runtime::Index::new(index)
+            }
+        });
+    }
+    // Every index is checked against the arrays as pushed, including those
+    // of the fields of the root object.
+    let mut index_checks = def
+        .fields
+        .iter()
+        .filter(|field| !field.hidden)
+        .filter_map(|field| {
+            let field_name = ident(&field.ident);
+            let stored = field.optional || field.default.is_some() || !matches!(field.ty, Type::Array(_) | Type::Map(..));
+            let value = if stored { quote!(value) } else { quote!(self.#field_name) };
+            let checks = json_index_checks(&field.ty, value, &quote!(self), &BTreeMap::new(), cx)?;
+            Some(if stored {
+                quote! {
+                    if let Some(ref value) = self.#field_name {
+                        #checks
+                    }
+                }
+            } else {
+                checks
+            })
+        })
+        .collect::<Vec<_>>();
+    for collection in &def.collections {
+        let field = ident(&collection.ident);
+        index_checks.push(quote! {
+            for json in &self.#field {
+                json.check_indices(lengths)?;
+            }
+        });
+    }
+    if !index_checks.is_empty() {
+        let fields = def.collections.iter().map(|collection| ident(&collection.ident)).collect::<Vec<_>>();
+        index_checks.insert(
+            0,
+            quote! {
+                let lengths = Lengths {
+                    #( #fields: self.#fields.len(), )*
+                };
+            },
+        );
+    }
+    quote! {
+        /// Builds a [`Document`] from the fields of the root object and the
+        /// objects pushed to its top-level arrays, handing out their indices.
+        #[derive(Debug, Default)]
+        pub struct DocumentBuilder {
+            #names_cfg
+            name: Option<String>,
+            #extras_cfg
+            extras: Option<#runtime::serde_json::Value>,
+            extensions: Option<#runtime::serde_json::Value>,
+            #(#fields)*
+        }
+
+        impl DocumentBuilder {
+            /// Returns a builder with no fields set and no objects.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Optional user-defined name for this object.
+            #names_cfg
+            pub fn name(&mut self, name: impl Into<String>) -> &mut Self {
+                self.name = Some(name.into());
+                self
+            }
+
+            /// Optional application specific data.
+            #extras_cfg
+            pub fn extras(&mut self, extras: #runtime::serde_json::Value) -> &mut Self {
+                self.extras = Some(extras);
+                self
+            }
+
+            /// Extension specific data.
+            pub fn extensions(&mut self, extensions: #runtime::serde_json::Value) -> &mut Self {
+                self.extensions = Some(extensions);
+                self
+            }
+
+            #(#setters)*
+
+            #(#methods)*
+
+            /// Fails if a required field is unset.
+            fn check_fields(&self) -> Result<(), #runtime::MissingField> {
+                #(#checks)*
+                Ok(())
+            }
+
+            /// Builds the document, filling unset fields with their defaults and
+            /// leaving the builder empty.
+            ///
+            /// Fails if a required field is unset, or if an index is out of range
+            /// of the objects pushed, leaving the builder unchanged.
+            pub fn build(&mut self) -> Result<Document, #runtime::BuildError> {
+                self.check_fields()?;
+                #(#index_checks)*
+                Ok(Document::from_json(#root {
+                    #names_cfg
+                    name: self.name.take(),
+                    #extras_cfg
+                    extras: self.extras.take(),
+                    extensions: self.extensions.take(),
+                    #(#initializers)*
+                }))
+            }
+        }
+    }
+}
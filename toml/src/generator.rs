@@ -0,0 +1,269 @@
+//! Configurable entry point to the code generator.
+
+use crate::codegen::{self, Options};
+use crate::link::Schema;
+use crate::schema::{self, Definition};
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+type Result<T> = std::result::Result<T, std::boxed::Box<dyn std::error::Error>>;
+
+/// First line of every generated file.
+const HEADER: &str = "// Generated by gltf-codegen-toml. Do not edit.";
+
+/// Generates the glTF types from a directory of schema files.
+///
+/// ```no_run
+/// // build.rs
+/// let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+/// gltf_codegen_toml::Generator::new("schema")
+///     .modules(["accessor", "buffer"])
+///     .extras_feature(None)
+///     .write_file(out_dir.join("gltf.rs"))
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generator {
+    schema_dir: PathBuf,
+    modules: Option<BTreeSet<String>>,
+    options: Options,
+}
+
+impl Generator {
+    /// Creates a generator for the schema files under `schema_dir`.
+    ///
+    /// The `templates` directory is skipped.
+    pub fn new<P: Into<PathBuf>>(schema_dir: P) -> Self {
+        Generator {
+            schema_dir: schema_dir.into(),
+            modules: None,
+            options: Options::default(),
+        }
+    }
+
+    /// Generates only the definitions in the given top-level modules.
+    ///
+    /// All modules are generated by default. Definitions without a module are
+    /// always generated. Every definition is still validated, and references
+    /// into a module that is not enabled are reported as errors.
+    pub fn modules<I, S>(&mut self, modules: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.modules = Some(modules.into_iter().map(|module| module.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Sets the derive macros applied to the JSON structs.
+    ///
+    /// Defaults to `Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize`.
+    /// `Deserialize` and `Serialize` refer to the serde derives re-exported by
+    /// the runtime crate.
+    pub fn struct_derives<I, S>(&mut self, derives: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.struct_derives = derives.into_iter().map(|name| name.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Sets the derive macros applied to the JSON enums.
+    ///
+    /// Defaults to `Clone, Copy, Debug, Eq, Hash, PartialEq`.
+    pub fn enum_derives<I, S>(&mut self, derives: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.enum_derives = derives.into_iter().map(|name| name.as_ref().to_string()).collect();
+        self
+    }
+
+    /// Sets the Cargo feature gating the `name` fields.
+    ///
+    /// Defaults to `names`. With `None` the fields are always present.
+    pub fn names_feature(&mut self, feature: Option<&str>) -> &mut Self {
+        self.options.names_feature = feature.map(str::to_string);
+        self
+    }
+
+    /// Sets the Cargo feature gating the `extras` fields.
+    ///
+    /// Defaults to `extras`. With `None` the fields are always present.
+    pub fn extras_feature(&mut self, feature: Option<&str>) -> &mut Self {
+        self.options.extras_feature = feature.map(str::to_string);
+        self
+    }
+
+    /// Sets the path of the runtime support crate used by the generated code.
+    ///
+    /// Defaults to `::gltf_codegen_runtime`. Set this when the runtime crate is
+    /// re-exported, e.g. `::gltf::runtime`.
+    pub fn runtime_crate(&mut self, path: &str) -> &mut Self {
+        self.options.runtime = path.to_string();
+        self
+    }
+
+    /// Returns the generated code as a single source file with inline modules.
+    ///
+    /// The output is suitable for `include!`.
+    pub fn generate(&self) -> Result<String> {
+        let root = self.module_tree()?;
+        let source = codegen::pretty(inline_module(&root))?;
+        Ok(format!("{}\n\n{}", HEADER, source))
+    }
+
+    /// Writes the output of `generate` to the file `path`.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.generate()?)?;
+        Ok(())
+    }
+
+    /// Writes the generated code as a crate source tree.
+    ///
+    /// Writes `lib.rs` to `out_dir` plus one file per module.
+    pub fn write_crate<P: AsRef<Path>>(&self, out_dir: P) -> Result<()> {
+        let out_dir = out_dir.as_ref();
+        let root = self.module_tree()?;
+        fs::create_dir_all(out_dir)?;
+        write_module_file(&root, &out_dir.join("lib.rs"), out_dir)
+    }
+
+    /// Returns the code generated for the single schema file `path`.
+    ///
+    /// References to other definitions are not resolved.
+    pub fn generate_definition<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let generated = codegen::generate(&schema::load(path.as_ref())?, &self.options)?;
+        let json = codegen::nest(Some(&generated.json_module()), generated.json);
+        let wrapper = codegen::nest(generated.module.as_deref(), generated.wrapper);
+        codegen::pretty(quote! {
+            #json
+            #wrapper
+        })
+    }
+
+    /// Returns every file the generated code depends on.
+    ///
+    /// These are the schema files plus the files they include, e.g. for
+    /// `cargo:rerun-if-changed`.
+    pub fn dependencies(&self) -> Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        collect_schema_files(&self.schema_dir, &mut files)?;
+        let mut includes = vec![];
+        for path in &files {
+            if let Some(ref include) = schema::load(path)?.meta().include {
+                includes.push(include.clone());
+            }
+        }
+        files.extend(includes);
+        Ok(files)
+    }
+
+    /// Returns `true` if `definition` belongs to an enabled module.
+    fn is_enabled(&self, definition: &Definition) -> bool {
+        match (&self.modules, &definition.meta().module) {
+            (Some(modules), Some(module)) => modules.contains(module.split("::").next().unwrap()),
+            _ => true,
+        }
+    }
+
+    /// Loads, links, and generates the enabled definitions.
+    fn module_tree(&self) -> Result<Module> {
+        let mut files = vec![];
+        collect_schema_files(&self.schema_dir, &mut files)?;
+        let definitions = files
+            .iter()
+            .map(|path| schema::load(path))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let schema = Schema::link(definitions)?.retain(|definition| self.is_enabled(definition))?;
+        let mut root = Module::default();
+        for definition in schema.definitions() {
+            let generated = codegen::generate(definition, &self.options)?;
+            root.descendant(&generated.json_module()).items.extend(generated.json);
+            let wrapper_module = match generated.module {
+                Some(ref module) => root.descendant(module),
+                None => &mut root,
+            };
+            wrapper_module.items.extend(generated.wrapper);
+        }
+        Ok(root)
+    }
+}
+
+/// A node in the generated module tree.
+#[derive(Default)]
+struct Module {
+    items: TokenStream,
+    submodules: BTreeMap<String, Module>,
+}
+
+impl Module {
+    /// Returns the descendant at the given module path, creating it if necessary.
+    fn descendant(&mut self, path: &str) -> &mut Module {
+        path.split("::").fold(self, |module, name| {
+            module.submodules.entry(name.to_string()).or_default()
+        })
+    }
+}
+
+/// Returns the items of `module` with its submodules declared inline.
+fn inline_module(module: &Module) -> TokenStream {
+    let names = module.submodules.keys().map(|name| codegen::ident(name));
+    let submodules = module.submodules.values().map(inline_module);
+    let items = &module.items;
+    quote! {
+        #(
+            pub mod #names {
+                #submodules
+            }
+        )*
+        #items
+    }
+}
+
+/// Writes `module` to `path` and its submodules to files in `dir`.
+fn write_module_file(module: &Module, path: &Path, dir: &Path) -> Result<()> {
+    let names = module.submodules.keys().map(|name| codegen::ident(name));
+    let items = &module.items;
+    let source = codegen::pretty(quote! {
+        #(pub mod #names;)*
+        #items
+    })?;
+    let mut output = fs::File::create(path)?;
+    writeln!(output, "{}", HEADER)?;
+    writeln!(output)?;
+    write!(output, "{}", source)?;
+    if !module.submodules.is_empty() {
+        fs::create_dir_all(dir)?;
+    }
+    for (name, submodule) in &module.submodules {
+        let path = dir.join(format!("{}.rs", name));
+        write_module_file(submodule, &path, &dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// Collects the schema files under `dir`, skipping the `templates` directory.
+pub(crate) fn collect_schema_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|err| format!("{}: {}", dir.display(), err))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            if path.file_name() != Some("templates".as_ref()) {
+                collect_schema_files(&path, files)?;
+            }
+        } else if path.extension() == Some("toml".as_ref()) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
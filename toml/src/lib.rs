@@ -0,0 +1,13 @@
+//! Generates the glTF JSON types and their wrappers from TOML schema files.
+//!
+//! Use [`Generator`] from a build script, or the `gltf-codegen-toml` binary
+//! from the command line. `gltf-codegen-toml lint <path>` checks schema files
+//! against the templates, see [`lint`].
+
+mod codegen;
+mod generator;
+mod link;
+pub mod lint;
+pub mod schema;
+
+pub use generator::Generator;
@@ -0,0 +1,484 @@
+//! Cross-reference resolution between schema definitions.
+
+use crate::schema::{Collection, Definition, Error, Errors, FieldDef, Literal, Type};
+use inflections::Inflect;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Kind of definition a type path is expected to resolve to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Struct,
+    Enum,
+}
+
+/// A set of definitions whose type paths all resolve.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    definitions: BTreeMap<String, Definition>,
+    /// Type paths that are defined but were left out by `retain`.
+    excluded: BTreeSet<String>,
+}
+
+impl Schema {
+    /// Resolves the type paths of `definitions` against each other.
+    ///
+    /// Also collects the top-level arrays into the root definition, if any.
+    ///
+    /// Reports every dangling or wrongly-kinded reference and every type path
+    /// or array name defined more than once.
+    pub fn link(definitions: Vec<Definition>) -> Result<Self, Errors> {
+        let mut errors = vec![];
+        let mut map = BTreeMap::new();
+        let mut root = None;
+        for definition in definitions {
+            let path = definition.meta().path();
+            if let Some(other) = map.get(&path).map(|other: &Definition| other.meta().file.clone()) {
+                errors.push(Error {
+                    file: definition.meta().file.clone(),
+                    key: Some("meta.ident".to_string()),
+                    position: None,
+                    message: format!("`{}` is already defined in {}", path, other.display()),
+                });
+                continue;
+            }
+            if let Definition::Root(_) = definition {
+                match root {
+                    Some(ref other) => errors.push(Error {
+                        file: definition.meta().file.clone(),
+                        key: Some("meta.kind".to_string()),
+                        position: None,
+                        message: format!("the root object is already defined by `{}`", other),
+                    }),
+                    None => root = Some(path.clone()),
+                }
+            }
+            map.insert(path, definition);
+        }
+        // Union enumerations are generated alongside their definition, so
+        // their paths must not clash with any other type.
+        let mut unions: BTreeMap<String, String> = BTreeMap::new();
+        for (path, definition) in &map {
+            if let Definition::Struct(ref def) = *definition {
+                if let Some(union_path) = def.union_path() {
+                    let other = map.get(&union_path).map(|other| other.meta().file.display().to_string());
+                    match other.or_else(|| unions.get(&union_path).cloned()) {
+                        Some(other) => errors.push(Error {
+                            file: def.meta.file.clone(),
+                            key: Some("union.ident".to_string()),
+                            position: None,
+                            message: format!("`{}` is already defined in {}", union_path, other),
+                        }),
+                        None => {
+                            unions.insert(union_path, format!("the union `{}`", path));
+                        },
+                    }
+                }
+            }
+        }
+        let mut collections: Vec<Collection> = vec![];
+        for definition in map.values() {
+            if let Definition::Struct(ref def) = *definition {
+                if let Some(ref array) = def.array {
+                    if let Some(other) = collections.iter().find(|collection| collection.json == *array) {
+                        errors.push(Error {
+                            file: def.meta.file.clone(),
+                            key: Some("meta.array".to_string()),
+                            position: None,
+                            message: format!("array `{}` is already used by `{}`", array, other.path),
+                        });
+                        continue;
+                    }
+                    collections.push(Collection {
+                        ident: array.to_snake_case(),
+                        json: array.clone(),
+                        path: def.meta.path(),
+                    });
+                }
+            }
+        }
+        if let Some(Definition::Root(def)) = root.and_then(|root| map.get_mut(&root)) {
+            for collection in &collections {
+                let reserved = ["name", "extras", "extensions"].contains(&collection.json.as_str());
+                if reserved || def.fields.iter().any(|field| field.json == collection.json) {
+                    errors.push(Error {
+                        file: def.meta.file.clone(),
+                        key: Some("fields".to_string()),
+                        position: None,
+                        message: format!("JSON name `{}` is already used by the array of `{}`", collection.json, collection.path),
+                    });
+                }
+            }
+            def.collections = collections;
+        }
+        let schema = Schema { definitions: map, excluded: BTreeSet::new() };
+        schema.check(errors)
+    }
+
+    /// Keeps only the definitions for which `f` returns `true`.
+    ///
+    /// The root definition drops its fields and arrays referring to removed
+    /// definitions. Every other reference from a kept definition to a removed
+    /// one is reported.
+    pub fn retain<F>(mut self, mut f: F) -> Result<Self, Errors>
+    where
+        F: FnMut(&Definition) -> bool,
+    {
+        let excluded = &mut self.excluded;
+        self.definitions.retain(|path, definition| {
+            let keep = f(definition);
+            if !keep {
+                excluded.insert(path.clone());
+            }
+            keep
+        });
+        let excluded = &self.excluded;
+        let is_excluded = |ty: &Type| match ty.element() {
+            Type::Index(ref path) | Type::Struct(ref path) | Type::Enum(ref path) => excluded.contains(path),
+            _ => false,
+        };
+        for definition in self.definitions.values_mut() {
+            if let Definition::Root(ref mut def) = *definition {
+                def.fields.retain(|field| !is_excluded(&field.ty));
+                def.collections.retain(|collection| !excluded.contains(&collection.path));
+            }
+        }
+        self.check(vec![])
+    }
+
+    /// Visits every definition in type path order.
+    pub fn definitions(&self) -> impl Iterator<Item = &Definition> {
+        self.definitions.values()
+    }
+
+    fn check(mut self, mut errors: Vec<Error>) -> Result<Self, Errors> {
+        for definition in self.definitions.values() {
+            for field in fields(definition) {
+                if let Err(message) = self.resolve_type(&field.ty) {
+                    errors.push(reference_error(definition, field, message));
+                } else if let Err(message) = self.resolve_default(field) {
+                    errors.push(Error {
+                        file: definition.meta().file.clone(),
+                        key: Some(format!("fields.{}.default", field.ident)),
+                        position: None,
+                        message,
+                    });
+                }
+            }
+            if let Definition::Struct(ref def) = *definition {
+                for variant in def.union.iter().flat_map(|union| &union.variants) {
+                    if let Err(message) = self.resolve_path("Struct", &variant.path, Kind::Struct) {
+                        errors.push(Error {
+                            file: def.meta.file.clone(),
+                            key: Some(format!("variants.{}.of", variant.ident)),
+                            position: variant.position,
+                            message,
+                        });
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            self.resolve_traits();
+            self.resolve_parents();
+            errors = self.resolve_scopes();
+        }
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(Errors(errors))
+        }
+    }
+
+    /// Checks that enum defaults, including those of array items, name
+    /// variants of the enum.
+    fn resolve_default(&self, field: &FieldDef) -> Result<(), String> {
+        let (path, default) = match (field.ty.element(), &field.default) {
+            (Type::Enum(ref path), Some(default)) => (path, default),
+            _ => return Ok(()),
+        };
+        let variants = match *default {
+            Literal::Array(ref values) => values.iter().collect(),
+            _ => vec![default],
+        };
+        if let Some(Definition::Enum(def)) = self.definitions.get(path) {
+            for variant in variants {
+                if let Literal::Variant(ref variant) = *variant {
+                    if !def.variants.iter().any(|x| x.ident == *variant) {
+                        return Err(format!("`{}` is not a variant of `{}`", variant, path));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Works out which structs can implement `Default`, and which can
+    /// implement `Eq` and `Hash`.
+    ///
+    /// Both depend on the structs referenced by the fields and union
+    /// variants, so this iterates
+    /// until nothing changes.
+    fn resolve_traits(&mut self) {
+        let mut defaultable = BTreeSet::new();
+        let mut unhashable = BTreeSet::new();
+        loop {
+            let count = (defaultable.len(), unhashable.len());
+            for (path, definition) in &self.definitions {
+                let (fields, collections, payloads) = match *definition {
+                    Definition::Struct(ref def) => (&def.fields, &[][..], def.union.as_ref().map(|union| &union.variants[..])),
+                    Definition::Root(ref def) => (&def.fields, &def.collections[..], None),
+                    Definition::Enum(_) => continue,
+                };
+                // A union always holds one of its payloads, so it has no
+                // natural default.
+                if payloads.is_none() && fields.iter().all(|field| has_default(field, &defaultable)) {
+                    defaultable.insert(path.clone());
+                }
+                if fields.iter().any(|field| !is_hashable(&field.ty, &unhashable))
+                    || collections.iter().any(|collection| unhashable.contains(&collection.path))
+                    || payloads.unwrap_or_default().iter().any(|variant| unhashable.contains(&variant.path))
+                {
+                    unhashable.insert(path.clone());
+                }
+            }
+            if (defaultable.len(), unhashable.len()) == count {
+                break;
+            }
+        }
+        for (path, definition) in &mut self.definitions {
+            match *definition {
+                Definition::Struct(ref mut def) => {
+                    def.defaultable = defaultable.contains(path);
+                    def.hashable = !unhashable.contains(path);
+                },
+                Definition::Root(ref mut def) => {
+                    def.defaultable = defaultable.contains(path);
+                    def.hashable = !unhashable.contains(path);
+                },
+                Definition::Enum(_) => {},
+            }
+        }
+    }
+
+    /// Works out which structs hold each struct directly, in a field, an
+    /// array of them, or a union variant.
+    fn resolve_parents(&mut self) {
+        let mut parents: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (path, definition) in &self.definitions {
+            if let Definition::Struct(ref def) = *definition {
+                let children = def.fields.iter().filter_map(|field| match field.ty {
+                    Type::Struct(ref child) => Some(child),
+                    Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => match **element {
+                        Type::Struct(ref child) => Some(child),
+                        _ => None,
+                    },
+                    _ => None,
+                });
+                let payloads = def.union.iter().flat_map(|union| &union.variants).map(|variant| &variant.path);
+                for child in children.chain(payloads) {
+                    parents.entry(child.clone()).or_default().insert(path.clone());
+                }
+            }
+        }
+        for (path, definition) in &mut self.definitions {
+            if let Definition::Struct(ref mut def) = *definition {
+                def.parents = parents.remove(path).unwrap_or_default().into_iter().collect();
+            }
+        }
+    }
+
+    /// Works out the parent array that each index into a struct outside the
+    /// top-level arrays refers to, e.g. the samplers of an animation for the
+    /// sampler of a channel.
+    ///
+    /// Reports indices whose parent holds no single such array.
+    fn resolve_scopes(&mut self) -> Vec<Error> {
+        let mut errors = vec![];
+        let mut scopes = vec![];
+        for (path, definition) in &self.definitions {
+            let def = match *definition {
+                Definition::Struct(ref def) => def,
+                _ => continue,
+            };
+            for (i, field) in def.fields.iter().enumerate() {
+                let target = match field.ty.element() {
+                    Type::Index(ref target) => target,
+                    _ => continue,
+                };
+                if let Some(Definition::Struct(ref target_def)) = self.definitions.get(target) {
+                    if target_def.array.is_some() {
+                        continue;
+                    }
+                }
+                let parent = match (def.array.as_ref(), &def.parents[..]) {
+                    (None, [parent]) => self.definitions.get(parent),
+                    _ => None,
+                };
+                let arrays = parent.map_or(vec![], |parent| {
+                    fields(parent)
+                        .iter()
+                        .filter(|array| matches!(array.ty, Type::Array(ref element) if **element == Type::Struct(target.clone())))
+                        .collect()
+                });
+                match arrays[..] {
+                    [array] if field.ty == Type::Index(target.clone()) => {
+                        scopes.push((path.clone(), i, def.parents[0].clone(), array.ident.clone()))
+                    },
+                    [_] => {
+                        let message = format!("indices into an array of the parent, like `{}`, are only supported in `Index` fields", target);
+                        errors.push(reference_error(definition, field, message));
+                    },
+                    _ => {
+                        let message = format!(
+                            "`{}` is neither in a top-level array nor in a single array of the one parent of `{}`",
+                            target, path
+                        );
+                        errors.push(reference_error(definition, field, message));
+                    },
+                }
+            }
+        }
+        for (path, i, parent, scope) in scopes {
+            let hidden = match self.definitions.get_mut(&path) {
+                Some(Definition::Struct(ref mut def)) => {
+                    def.fields[i].scope = Some(scope.clone());
+                    def.fields[i].hidden
+                },
+                _ => continue,
+            };
+            // Editing wrappers of the holder are given the lengths of the
+            // arrays that its exposed fields index.
+            if let Some(Definition::Struct(ref mut def)) = self.definitions.get_mut(&parent) {
+                let scopes = def.scopes.entry(path).or_default();
+                if !hidden && !scopes.contains(&scope) {
+                    scopes.push(scope);
+                    scopes.sort();
+                }
+            }
+        }
+        errors
+    }
+
+    fn resolve_type(&self, ty: &Type) -> Result<(), String> {
+        match *ty {
+            Type::Index(ref path) => self.resolve_path("Index", path, Kind::Struct),
+            Type::Struct(ref path) => self.resolve_path("Struct", path, Kind::Struct),
+            Type::Enum(ref path) => self.resolve_path("Enum", path, Kind::Enum),
+            Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => self.resolve_type(element),
+            Type::Map(ref key, ref value) => self.resolve_type(key).and_then(|()| self.resolve_type(value)),
+            Type::Integer
+            | Type::Integer64
+            | Type::SignedInteger
+            | Type::Float
+            | Type::Double
+            | Type::Number
+            | Type::Bool
+            | Type::String
+            | Type::Any
+            | Type::Special(_) => Ok(()),
+        }
+    }
+
+    fn resolve_path(&self, ty: &str, path: &str, expected: Kind) -> Result<(), String> {
+        let found = match self.definitions.get(path) {
+            Some(Definition::Struct(_)) if expected == Kind::Struct => return Ok(()),
+            Some(Definition::Enum(_)) if expected == Kind::Enum => return Ok(()),
+            Some(Definition::Struct(_)) => "a struct",
+            Some(Definition::Enum(_)) => "an enum",
+            Some(Definition::Root(_)) => "the root object",
+            None if is_scalar(path) => "a scalar type",
+            None if self.excluded.contains(path) => {
+                return Err(format!("`{}` refers to `{}`, whose module is not enabled", ty, path));
+            },
+            None => return Err(format!("`{}` refers to `{}`, which is not defined", ty, path)),
+        };
+        let expected = match expected {
+            Kind::Struct => "a struct",
+            Kind::Enum => "an enum",
+        };
+        Err(format!("`{}` refers to `{}`, which is {}, not {}", ty, path, found, expected))
+    }
+}
+
+/// Returns the fields declared by `definition`.
+fn fields(definition: &Definition) -> &[FieldDef] {
+    match *definition {
+        Definition::Struct(ref def) => &def.fields,
+        Definition::Root(ref def) => &def.fields,
+        Definition::Enum(_) => &[],
+    }
+}
+
+/// Returns `true` if `field` has a default value, given the paths of the
+/// structs known to have one.
+fn has_default(field: &FieldDef, defaultable: &BTreeSet<String>) -> bool {
+    field.optional || field.default.is_some() || implements_default(&field.ty, defaultable)
+}
+
+/// Returns `true` if `ty` implements `Default`, given the paths of the
+/// structs known to.
+fn implements_default(ty: &Type, defaultable: &BTreeSet<String>) -> bool {
+    match *ty {
+        Type::Integer
+        | Type::Integer64
+        | Type::SignedInteger
+        | Type::Float
+        | Type::Double
+        | Type::Bool
+        | Type::String
+        | Type::Any
+        | Type::Array(_)
+        | Type::Map(..) => true,
+        // `Default` is implemented for arrays of up to 32 elements.
+        Type::FixedSizeArray(ref element, n) => n <= 32 && implements_default(element, defaultable),
+        Type::Struct(ref path) => defaultable.contains(path),
+        Type::Index(_) | Type::Enum(_) | Type::Number | Type::Special(_) => false,
+    }
+}
+
+/// Returns `true` if `ty` implements `Eq` and `Hash`, given the paths of the
+/// structs known not to.
+///
+/// Special types are opaque, so they are assumed not to.
+fn is_hashable(ty: &Type, unhashable: &BTreeSet<String>) -> bool {
+    match *ty {
+        Type::Float | Type::Double | Type::Special(_) => false,
+        Type::Struct(ref path) => !unhashable.contains(path),
+        Type::Array(ref element) | Type::FixedSizeArray(ref element, _) => is_hashable(element, unhashable),
+        Type::Map(ref key, ref value) => is_hashable(key, unhashable) && is_hashable(value, unhashable),
+        Type::Integer
+        | Type::Integer64
+        | Type::SignedInteger
+        | Type::Number
+        | Type::Bool
+        | Type::String
+        | Type::Any
+        | Type::Index(_)
+        | Type::Enum(_) => true,
+    }
+}
+
+/// Returns `true` if `name` is one of the scalar field types.
+fn is_scalar(name: &str) -> bool {
+    [
+        "Integer",
+        "Integer64",
+        "SignedInteger",
+        "Float",
+        "Double",
+        "Number",
+        "Bool",
+        "String",
+        "Any",
+    ]
+    .contains(&name)
+}
+
+fn reference_error(definition: &Definition, field: &FieldDef, message: String) -> Error {
+    Error {
+        file: definition.meta().file.clone(),
+        key: Some(format!("fields.{}.of", field.ident)),
+        position: field.position,
+        message,
+    }
+}
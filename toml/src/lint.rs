@@ -0,0 +1,365 @@
+//! Checks schema files against the conventions of `schema/templates`.
+//!
+//! Loading stops at the first error and ignores keys it does not read, so a
+//! misspelt `optinal = true` goes unnoticed. Linting reports every problem it
+//! finds, with the position of the offending entry.
+
+use crate::generator::collect_schema_files;
+use crate::link::Schema;
+use crate::schema::{self, is_ident, Error};
+use inflections::Inflect;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Keys read from the `meta` table, by definition kind.
+const META_KEYS: &[(&str, &[&str])] = &[
+    ("Struct", &["kind", "ident", "module", "docs", "include", "array", "derives"]),
+    ("Union", &["kind", "ident", "module", "docs", "include", "array", "derives"]),
+    ("Enum", &["kind", "ident", "module", "docs", "of", "include", "derives"]),
+    ("Root", &["kind", "ident", "docs", "include", "derives"]),
+];
+
+/// Tables read besides `meta`, by definition kind.
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("Struct", &["fields"]),
+    ("Union", &["fields", "union", "variants"]),
+    ("Enum", &["values"]),
+    ("Root", &["fields"]),
+];
+
+/// Keys read from the entries of `fields` tables.
+const FIELD_KEYS: &[&str] = &[
+    "ty",
+    "of",
+    "json",
+    "docs",
+    "optional",
+    "default",
+    "minimum",
+    "exclusive_minimum",
+    "maximum",
+    "min_items",
+    "max_items",
+    "unique_items",
+    "hidden",
+];
+
+/// Keys read from the inline tables of element types, e.g. the `of` table of
+/// an `Array` field.
+const ELEMENT_KEYS: &[&str] = &["ty", "of", "n"];
+
+/// Keys read from the `of` tables of `Map` types.
+const MAP_KEYS: &[&str] = &["key", "value"];
+
+/// Keys read from the `union` table.
+const UNION_KEYS: &[&str] = &["ident", "tag", "docs"];
+
+/// Keys read from the entries of `values` tables.
+const VALUE_KEYS: &[&str] = &["value", "docs"];
+
+/// Keys read from the entries of `variants` tables.
+const VARIANT_KEYS: &[&str] = &["value", "of", "docs"];
+
+/// Lints the schema file at `path`, or every schema file under the directory
+/// at `path`, skipping the `templates` directory.
+///
+/// Files that pass are checked against the template rules and, for
+/// directories, linked together.
+pub fn lint<P: AsRef<Path>>(path: P) -> Vec<Error> {
+    let path = path.as_ref();
+    let mut files = vec![];
+    if path.is_dir() {
+        if let Err(err) = collect_schema_files(path, &mut files) {
+            return vec![Error { file: path.to_path_buf(), key: None, position: None, message: err.to_string() }];
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    let mut errors = vec![];
+    let mut definitions = vec![];
+    for file in &files {
+        let mut diagnostics = lint_file(file);
+        if diagnostics.is_empty() {
+            match schema::load(file) {
+                Ok(definition) => definitions.push(definition),
+                Err(err) => diagnostics.push(err),
+            }
+        }
+        errors.extend(diagnostics);
+    }
+    if path.is_dir() && errors.is_empty() {
+        if let Err(err) = Schema::link(definitions) {
+            errors.extend(err.0);
+        }
+    }
+    errors
+}
+
+/// Checks the keys, names, and documentation of a single schema file.
+fn lint_file(path: &Path) -> Vec<Error> {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => return vec![Error { file: path.to_path_buf(), key: None, position: None, message: err.to_string() }],
+    };
+    let document = match toml::from_str::<toml::value::Table>(&source) {
+        Ok(document) => document,
+        Err(err) => return vec![schema::toml_error(path, &err)],
+    };
+    let mut linter = Linter { file: path, positions: positions(&source), errors: vec![] };
+    linter.document(&document);
+    let mut errors = linter.errors;
+    errors.sort_by_key(|error| error.position);
+    errors
+}
+
+/// Collects the problems of one schema file.
+struct Linter<'a> {
+    file: &'a Path,
+    /// Positions of the keys and table headers in the source.
+    positions: HashMap<String, (usize, usize)>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Linter<'a> {
+    /// Reports a problem with the entry at `key`.
+    fn report<S: Into<String>>(&mut self, key: &str, message: S) {
+        // Entries of inline tables are found through the key of the table.
+        let mut prefix = key;
+        let position = loop {
+            if let Some(position) = self.positions.get(prefix) {
+                break Some(*position);
+            }
+            match prefix.rfind('.') {
+                Some(i) => prefix = &prefix[..i],
+                None => break None,
+            }
+        };
+        self.errors.push(Error {
+            file: self.file.to_path_buf(),
+            key: Some(key.to_string()),
+            position,
+            message: message.into(),
+        });
+    }
+
+    /// Reports the keys of `table` at `key` that are not in `known`.
+    ///
+    /// `kind` names the definition kind for keys that are read elsewhere.
+    fn keys(&mut self, key: &str, table: &toml::value::Table, known: &[&str], kind: &str) {
+        for name in table.keys().filter(|name| !known.contains(&name.as_str())) {
+            let read_elsewhere = META_KEYS.iter().any(|(_, keys)| keys.contains(&name.as_str()))
+                || FIELD_KEYS.contains(&name.as_str());
+            let message = if read_elsewhere && key == "meta" {
+                format!("`{}` is not used by `{}` definitions", name, kind)
+            } else if read_elsewhere && key.starts_with("fields.") && key.matches('.').count() == 1 {
+                format!("`{}` is not used by the fields of `{}` definitions", name, kind)
+            } else {
+                format!("unknown key `{}`", name)
+            };
+            self.report(&format!("{}.{}", key, name), message);
+        }
+    }
+
+    /// Reports the unknown keys of the inline `of` table at `key` of a field
+    /// or element of type `ty`, and of the element tables within it.
+    fn of_table(&mut self, key: &str, ty: Option<&str>, of: &toml::Value, kind: &str) {
+        let table = match of.as_table() {
+            Some(table) => table,
+            // Type names and paths are checked when the file is loaded.
+            None => return,
+        };
+        if ty != Some("Map") {
+            self.element(key, table, kind);
+            return;
+        }
+        self.keys(key, table, MAP_KEYS, kind);
+        for name in MAP_KEYS {
+            if let Some(element) = table.get(*name).and_then(toml::Value::as_table) {
+                self.element(&format!("{}.{}", key, name), element, kind);
+            }
+        }
+    }
+
+    /// Reports the unknown keys of the element table at `key`, and of the
+    /// tables nested within it.
+    fn element(&mut self, key: &str, table: &toml::value::Table, kind: &str) {
+        self.keys(key, table, ELEMENT_KEYS, kind);
+        if let Some(of) = table.get("of") {
+            let ty = table.get("ty").and_then(toml::Value::as_str);
+            self.of_table(&format!("{}.of", key), ty, of, kind);
+        }
+    }
+
+    /// Reports a missing or empty `docs` entry in `table` at `key`.
+    fn docs(&mut self, key: &str, table: &toml::value::Table) {
+        match table.get("docs") {
+            Some(toml::Value::String(docs)) if !docs.trim().is_empty() => {},
+            Some(toml::Value::String(_)) => self.report(&format!("{}.docs", key), "empty `docs`"),
+            Some(_) => self.report(&format!("{}.docs", key), "expected a string"),
+            None => self.report(key, "missing `docs`"),
+        }
+    }
+
+    /// Returns the entries of the table at `key`, reporting other values.
+    fn table<'t>(&mut self, key: &str, value: &'t toml::Value) -> Option<&'t toml::value::Table> {
+        let table = value.as_table();
+        if table.is_none() {
+            self.report(key, "expected a table");
+        }
+        table
+    }
+
+    fn document(&mut self, document: &toml::value::Table) {
+        let meta = match document.get("meta") {
+            Some(meta) => match self.table("meta", meta) {
+                Some(meta) => meta,
+                None => return,
+            },
+            None => {
+                self.report("meta", "missing `meta` table");
+                return;
+            },
+        };
+        let kind = meta.get("kind").and_then(toml::Value::as_str).unwrap_or_default();
+        let (known_meta, sections) = match META_KEYS.iter().zip(SECTIONS).find(|((name, _), _)| *name == kind) {
+            Some(((_, known_meta), (_, sections))) => (*known_meta, *sections),
+            None => {
+                let message = format!("unknown definition kind `{}`, expected `Struct`, `Union`, `Enum`, or `Root`", kind);
+                self.report("meta.kind", message);
+                return;
+            },
+        };
+        for name in document.keys().filter(|name| *name != "meta" && !sections.contains(&name.as_str())) {
+            let message = if SECTIONS.iter().any(|(_, sections)| sections.contains(&name.as_str())) {
+                format!("`{}` is not used by `{}` definitions", name, kind)
+            } else {
+                format!("unknown key `{}`", name)
+            };
+            self.report(name, message);
+        }
+        self.keys("meta", meta, known_meta, kind);
+        self.docs("meta", meta);
+        if let Some(ident) = meta.get("ident").and_then(toml::Value::as_str) {
+            if !is_pascal_case(ident) {
+                self.report("meta.ident", format!("`{}` is not PascalCase", ident));
+            }
+        }
+        if let Some(module) = meta.get("module").and_then(toml::Value::as_str) {
+            if !module.split("::").all(is_snake_case) {
+                self.report("meta.module", format!("`{}` is not a snake_case module path", module));
+            }
+        }
+
+        // JSON names already taken, including those of the boilerplate entries.
+        let mut json_names: HashMap<String, String> = ["name", "extras", "extensions"]
+            .iter()
+            .map(|name| (name.to_string(), format!("`{}`", name)))
+            .collect();
+        let fields = document.get("fields").filter(|_| sections.contains(&"fields"));
+        if let Some(fields) = fields.and_then(|fields| self.table("fields", fields)) {
+            let mut fields = fields.iter().collect::<Vec<_>>();
+            // Later duplicates are reported, so go in declaration order.
+            fields.sort_by_key(|(ident, _)| self.positions.get(&format!("fields.{}", ident)).copied());
+            for (ident, field) in fields {
+                let key = format!("fields.{}", ident);
+                let field = match self.table(&key, field) {
+                    Some(field) => field,
+                    None => continue,
+                };
+                // The root wrapper has no field methods to hide.
+                let known = FIELD_KEYS.iter().copied().filter(|name| kind != "Root" || *name != "hidden").collect::<Vec<_>>();
+                self.keys(&key, field, &known, kind);
+                if let Some(of) = field.get("of") {
+                    let ty = field.get("ty").and_then(toml::Value::as_str);
+                    self.of_table(&format!("{}.of", key), ty, of, kind);
+                }
+                self.docs(&key, field);
+                if !is_snake_case(ident) {
+                    self.report(&key, format!("field name `{}` is not snake_case", ident));
+                }
+                let json = match field.get("json") {
+                    Some(toml::Value::String(json)) => json.clone(),
+                    Some(_) => {
+                        self.report(&format!("{}.json", key), "expected a string");
+                        continue;
+                    },
+                    None => {
+                        self.report(&key, "missing `json`");
+                        ident.to_camel_case()
+                    },
+                };
+                if let Some(other) = json_names.insert(json.clone(), format!("field `{}`", ident)) {
+                    self.report(&format!("{}.json", key), format!("JSON name `{}` is already used by {}", json, other));
+                }
+            }
+        }
+        let union = document.get("union").filter(|_| sections.contains(&"union"));
+        if let Some(union) = union.and_then(|union| self.table("union", union)) {
+            self.keys("union", union, UNION_KEYS, kind);
+            self.docs("union", union);
+            if let Some(ident) = union.get("ident").and_then(toml::Value::as_str) {
+                if !is_snake_case(ident) {
+                    self.report("union.ident", format!("`{}` is not snake_case", ident));
+                }
+            }
+            if let Some(tag) = union.get("tag").and_then(toml::Value::as_str) {
+                if let Some(other) = json_names.insert(tag.to_string(), "the discriminator".to_string()) {
+                    self.report("union.tag", format!("JSON name `{}` is already used by {}", tag, other));
+                }
+            }
+        }
+        for (section, known) in [("values", VALUE_KEYS), ("variants", VARIANT_KEYS)] {
+            let entries = document.get(section).filter(|_| sections.contains(&section));
+            let entries = match entries.and_then(|entries| self.table(section, entries)) {
+                Some(entries) => entries,
+                None => continue,
+            };
+            for (ident, entry) in entries {
+                let key = format!("{}.{}", section, ident);
+                let entry = match self.table(&key, entry) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                self.keys(&key, entry, known, kind);
+                self.docs(&key, entry);
+                if !is_pascal_case(ident) {
+                    self.report(&key, format!("variant name `{}` is not PascalCase", ident));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the positions of the keys and table headers in `source`, by full
+/// key, e.g. `fields.zfar.optional`.
+///
+/// This is a line-based scan, which is enough for the layout of the schema
+/// files: one key or header per line, with inline tables on a single line.
+fn positions(source: &str) -> HashMap<String, (usize, usize)> {
+    let mut positions = HashMap::new();
+    let mut table = String::new();
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+        if trimmed.starts_with('[') {
+            table = trimmed.trim_end().trim_matches(|c| c == '[' || c == ']').trim().to_string();
+            positions.insert(table.clone(), (i + 1, column));
+        } else if let Some((key, _)) = trimmed.split_once('=') {
+            let key = key.trim().trim_matches('"');
+            if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.') {
+                let key = if table.is_empty() { key.to_string() } else { format!("{}.{}", table, key) };
+                positions.insert(key, (i + 1, column));
+            }
+        }
+    }
+    positions
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    is_ident(name) && name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_')
+}
+
+fn is_snake_case(name: &str) -> bool {
+    is_ident(name) && *name == name.to_snake_case()
+}
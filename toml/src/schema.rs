@@ -0,0 +1,1222 @@
+//! Typed model of the TOML schema definitions.
+//!
+//! See `schema/templates` for the grammar.
+
+use inflections::Inflect;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::{error, fmt, fs};
+use toml::Spanned;
+
+/// An error in a schema definition file.
+#[derive(Clone, Debug)]
+pub struct Error {
+    /// The offending schema file.
+    pub file: PathBuf,
+    /// Table path of the offending entry, e.g. `fields.zfar.optional`.
+    pub key: Option<String>,
+    /// One-based line and column of the offending entry.
+    pub position: Option<(usize, usize)>,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        if let Some(ref key) = self.key {
+            write!(f, ": `{}`", key)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+/// A collection of errors, reported one per line.
+#[derive(Clone, Debug)]
+pub struct Errors(pub Vec<Error>);
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Errors {}
+
+/// A single schema definition, i.e. the contents of one TOML file.
+#[derive(Clone, Debug)]
+pub enum Definition {
+    /// A JSON object, possibly a tagged union.
+    Struct(StructDef),
+    /// An enumeration of JSON values.
+    Enum(EnumDef),
+    /// The top-level JSON object.
+    Root(RootDef),
+}
+
+impl Definition {
+    /// Returns the metadata common to all definition kinds.
+    pub fn meta(&self) -> &Meta {
+        match *self {
+            Definition::Struct(ref def) => &def.meta,
+            Definition::Enum(ref def) => &def.meta,
+            Definition::Root(ref def) => &def.meta,
+        }
+    }
+}
+
+/// Metadata common to all definition kinds.
+#[derive(Clone, Debug)]
+pub struct Meta {
+    /// Type identifier, e.g. `Accessor`.
+    pub ident: String,
+    /// Module path, e.g. `accessor`. May be nested, e.g. `foo::bar`.
+    pub module: Option<String>,
+    /// Documentation for the type declaration.
+    pub docs: String,
+    /// Hand-written code to include alongside the generated wrapper.
+    ///
+    /// Resolved relative to the schema file.
+    pub include: Option<PathBuf>,
+    /// Derive macros applied in addition to the generator's, e.g. `Copy`.
+    pub derives: Vec<String>,
+    /// Schema file the definition was loaded from.
+    pub file: PathBuf,
+}
+
+impl Meta {
+    /// Returns the qualified type path, e.g. `accessor::Accessor`.
+    pub fn path(&self) -> String {
+        match self.module {
+            Some(ref module) => format!("{}::{}", module, self.ident),
+            None => self.ident.clone(),
+        }
+    }
+}
+
+/// A `kind = "Struct"` or `kind = "Union"` definition.
+#[derive(Clone, Debug)]
+pub struct StructDef {
+    /// Type metadata.
+    pub meta: Meta,
+    /// Distinguishing fields, excluding `name`, `extras`, and `extensions`.
+    pub fields: Vec<FieldDef>,
+    /// Name of the top-level array holding these objects, e.g. `bufferViews`.
+    pub array: Option<String>,
+    /// Discriminated payload of a union definition.
+    pub union: Option<UnionDef>,
+    /// Whether every field has a default value, so that the struct can
+    /// implement `Default`.
+    ///
+    /// Filled in when the schema is linked.
+    pub defaultable: bool,
+    /// Whether no field holds a floating point or special type, so that the
+    /// struct can implement `Eq` and `Hash`.
+    ///
+    /// Filled in when the schema is linked.
+    pub hashable: bool,
+    /// Type paths of the struct definitions holding these objects in a field
+    /// or union variant, so that wrappers can refer back to them.
+    ///
+    /// Filled in when the schema is linked.
+    pub parents: Vec<String>,
+    /// Identifiers of the arrays of these objects that `Index` fields of the
+    /// objects they hold refer to, sorted, by type path of the holding
+    /// struct, e.g. `samplers` for `animation::Channel`.
+    ///
+    /// Filled in when the schema is linked.
+    pub scopes: BTreeMap<String, Vec<String>>,
+}
+
+impl StructDef {
+    /// Returns the type path of the union enumeration, if any, e.g.
+    /// `camera::Projection`.
+    pub fn union_path(&self) -> Option<String> {
+        self.union.as_ref().map(|union| match self.meta.module {
+            Some(ref module) => format!("{}::{}", module, union.enum_ident()),
+            None => union.enum_ident(),
+        })
+    }
+}
+
+/// The `[union]` table of a union definition.
+///
+/// In JSON data, a discriminator names the variant and a property of the
+/// same name holds its payload, e.g. `"type": "perspective"` alongside
+/// `"perspective": { ... }`.
+#[derive(Clone, Debug)]
+pub struct UnionDef {
+    /// Rust field identifier, e.g. `projection`.
+    pub ident: String,
+    /// Name of the discriminator in JSON data, e.g. `type`.
+    pub tag: String,
+    /// Documentation for the field and enumeration declarations.
+    pub docs: String,
+    /// Union variants.
+    pub variants: Vec<UnionVariantDef>,
+}
+
+impl UnionDef {
+    /// Returns the identifier of the enumeration, e.g. `Projection`.
+    pub fn enum_ident(&self) -> String {
+        self.ident.to_pascal_case()
+    }
+}
+
+/// A `[variants.*]` entry of a union definition.
+#[derive(Clone, Debug)]
+pub struct UnionVariantDef {
+    /// Rust variant identifier, e.g. `Perspective`.
+    pub ident: String,
+    /// Discriminator value, which also names the payload in JSON data, e.g.
+    /// `perspective`.
+    pub value: String,
+    /// Type path of the payload struct, e.g. `camera::Perspective`.
+    pub path: String,
+    /// Documentation for the variant declaration.
+    pub docs: String,
+    /// One-based line and column of the payload's type declaration.
+    pub position: Option<(usize, usize)>,
+}
+
+/// A `kind = "Root"` definition.
+#[derive(Clone, Debug)]
+pub struct RootDef {
+    /// Type metadata.
+    pub meta: Meta,
+    /// Fields declared in the schema file.
+    pub fields: Vec<FieldDef>,
+    /// Top-level arrays, one per struct definition with an `array` key.
+    ///
+    /// Filled in when the schema is linked.
+    pub collections: Vec<Collection>,
+    /// Whether every field has a default value.
+    ///
+    /// Filled in when the schema is linked.
+    pub defaultable: bool,
+    /// Whether no field or array element holds a floating point or special
+    /// type.
+    ///
+    /// Filled in when the schema is linked.
+    pub hashable: bool,
+}
+
+impl RootDef {
+    /// Returns the top-level object with one array field per collection.
+    pub fn struct_def(&self) -> StructDef {
+        let collections = self.collections.iter().map(|collection| FieldDef {
+            ident: collection.ident.clone(),
+            ty: Type::Array(Box::new(Type::Struct(collection.path.clone()))),
+            json: collection.json.clone(),
+            docs: format!("Every `{}` in the document.", collection.path),
+            optional: false,
+            default: None,
+            constraints: Constraints::default(),
+            hidden: false,
+            scope: None,
+            position: None,
+        });
+        StructDef {
+            meta: self.meta.clone(),
+            fields: self.fields.iter().cloned().chain(collections).collect(),
+            array: None,
+            union: None,
+            defaultable: self.defaultable,
+            hashable: self.hashable,
+            parents: vec![],
+            scopes: BTreeMap::new(),
+        }
+    }
+}
+
+/// A top-level array of the root object.
+#[derive(Clone, Debug)]
+pub struct Collection {
+    /// Rust field identifier, e.g. `buffer_views`.
+    pub ident: String,
+    /// Name of the array in JSON data, e.g. `bufferViews`.
+    pub json: String,
+    /// Type path of the elements, e.g. `buffer::View`.
+    pub path: String,
+}
+
+/// A `[fields.*]` entry of a struct definition.
+#[derive(Clone, Debug)]
+pub struct FieldDef {
+    /// Rust field identifier, e.g. `buffer_view`.
+    pub ident: String,
+    /// Field data type.
+    pub ty: Type,
+    /// Name of the field in JSON data, e.g. `bufferView`.
+    pub json: String,
+    /// Documentation for the field declaration.
+    pub docs: String,
+    /// Whether the field may be absent from JSON data.
+    pub optional: bool,
+    /// Value assumed when the field is absent from JSON data.
+    pub default: Option<Literal>,
+    /// Rules checked by the generated validation code.
+    pub constraints: Constraints,
+    /// Whether the field is excluded from the generated wrapper.
+    pub hidden: bool,
+    /// For an index into an array of the parent object rather than of the
+    /// root object, the identifier of that array, e.g. `samplers`.
+    ///
+    /// Filled in when the schema is linked.
+    pub scope: Option<String>,
+    /// One-based line and column of the field's type declaration, if any.
+    pub position: Option<(usize, usize)>,
+}
+
+/// Rules on the value of a field, after those of JSON schema.
+///
+/// Bounds apply to numbers, or to every item of an array of numbers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Constraints {
+    /// Inclusive lower bound, e.g. `4` for `byteStride`.
+    pub minimum: Option<Literal>,
+    /// Exclusive lower bound, e.g. `0.0` for `yfov`.
+    pub exclusive_minimum: Option<Literal>,
+    /// Inclusive upper bound, e.g. `252` for `byteStride`.
+    pub maximum: Option<Literal>,
+    /// Minimum number of array items.
+    pub min_items: Option<usize>,
+    /// Maximum number of array items.
+    pub max_items: Option<usize>,
+    /// Whether array items must be distinct.
+    pub unique_items: bool,
+}
+
+/// Field data type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    /// `u32`.
+    Integer,
+    /// `u64`, e.g. for the byte length of large buffers.
+    Integer64,
+    /// `i32`.
+    SignedInteger,
+    /// `f32`.
+    Float,
+    /// `f64`, e.g. for accessor bounds on large coordinates.
+    Double,
+    /// A JSON number that may or may not be an integer.
+    Number,
+    /// `bool`.
+    Bool,
+    /// `String`.
+    String,
+    /// Arbitrary JSON data.
+    Any,
+    /// Index of a top-level object, e.g. `Index("accessor::Accessor")`.
+    Index(String),
+    /// Nested object, e.g. `Struct("animation::Target")`.
+    Struct(String),
+    /// Enumeration, e.g. `Enum("accessor::DataType")`.
+    Enum(String),
+    /// Variable length array.
+    Array(Box<Type>),
+    /// Fixed length array.
+    FixedSizeArray(Box<Type>, usize),
+    /// JSON object with typed keys and values, kept in document order.
+    Map(Box<Type>, Box<Type>),
+    /// Verbatim Rust type.
+    Special(String),
+}
+
+impl Type {
+    /// Returns the name of the type as written in the schema.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Type::Integer => "Integer",
+            Type::Integer64 => "Integer64",
+            Type::SignedInteger => "SignedInteger",
+            Type::Float => "Float",
+            Type::Double => "Double",
+            Type::Number => "Number",
+            Type::Bool => "Bool",
+            Type::String => "String",
+            Type::Any => "Any",
+            Type::Index(_) => "Index",
+            Type::Struct(_) => "Struct",
+            Type::Enum(_) => "Enum",
+            Type::Array(_) => "Array",
+            Type::FixedSizeArray(..) => "FixedSizeArray",
+            Type::Map(..) => "Map",
+            Type::Special(_) => "Special",
+        }
+    }
+
+    /// Returns `true` for the numeric types with a fixed Rust representation.
+    pub fn is_numeric(&self) -> bool {
+        matches!(*self, Type::Integer | Type::Integer64 | Type::SignedInteger | Type::Float | Type::Double)
+    }
+
+    /// Returns `true` if the Rust type is `Copy`, i.e. numeric and boolean
+    /// types and fixed size arrays of them.
+    pub fn is_copy(&self) -> bool {
+        match *self {
+            Type::FixedSizeArray(ref element, _) => element.is_copy(),
+            ref ty => ty.is_numeric() || *ty == Type::Bool,
+        }
+    }
+
+    /// Returns the innermost element type of arrays and value type of maps, or
+    /// the type itself otherwise.
+    pub fn element(&self) -> &Type {
+        match *self {
+            Type::Array(ref element) | Type::FixedSizeArray(ref element, _) | Type::Map(_, ref element) => {
+                element.element()
+            },
+            _ => self,
+        }
+    }
+}
+
+/// A literal value in a definition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    /// Integer literal, e.g. `5126`.
+    Integer(i64),
+    /// Floating point literal, e.g. `1.0`.
+    Float(f64),
+    /// Boolean literal.
+    Bool(bool),
+    /// String literal, e.g. `"SCALAR"`.
+    String(String),
+    /// Enum variant identifier, e.g. `Repeat`.
+    Variant(String),
+    /// Array literal, e.g. `[1.0, 1.0, 1.0]`.
+    Array(Vec<Literal>),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Literal::Integer(value) => write!(f, "{}", value),
+            Literal::Float(value) => write!(f, "{:?}", value),
+            Literal::Bool(value) => write!(f, "{}", value),
+            Literal::String(ref value) => write!(f, "{:?}", value),
+            Literal::Variant(ref value) => write!(f, "{}", value),
+            Literal::Array(ref values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            },
+        }
+    }
+}
+
+/// A `kind = "Enum"` definition.
+#[derive(Clone, Debug)]
+pub struct EnumDef {
+    /// Type metadata.
+    pub meta: Meta,
+    /// JSON representation of the enumeration.
+    pub repr: Repr,
+    /// Enumeration variants.
+    pub variants: Vec<VariantDef>,
+}
+
+/// JSON representation of an enumeration.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Repr {
+    /// Variants are JSON integers, e.g. GL constants.
+    Integer,
+    /// Variants are JSON strings.
+    String,
+    /// Variants are JSON strings, some of which match a pattern such as
+    /// `"COLOR_{u32}"`.
+    Pattern,
+}
+
+/// Data carried by a pattern variant.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Payload {
+    /// The `{u32}` suffix of e.g. `"TEXCOORD_{u32}"`.
+    Integer,
+    /// The `{String}` suffix of e.g. `"_{String}"`.
+    String,
+}
+
+/// A `[values.*]` entry of an enum definition.
+#[derive(Clone, Debug)]
+pub struct VariantDef {
+    /// Rust variant identifier, e.g. `Vec3`.
+    pub ident: String,
+    /// Corresponding JSON value, e.g. `"VEC3"`, or the prefix of a pattern
+    /// variant, e.g. `"COLOR_"`.
+    pub value: Literal,
+    /// Data matched after the prefix, for pattern variants.
+    pub payload: Option<Payload>,
+    /// Documentation for the variant declaration.
+    pub docs: String,
+}
+
+#[derive(Deserialize)]
+struct RawDefinition {
+    meta: RawMeta,
+    fields: Option<Table<RawField>>,
+    values: Option<Table<RawVariant>>,
+    union: Option<RawUnion>,
+    variants: Option<Table<RawUnionVariant>>,
+}
+
+/// The entries of a TOML table in the order they are written.
+///
+/// Declaration order determines the order of fields and variants in the
+/// generated code, so it must survive parsing.
+struct Table<T>(Vec<(String, T)>);
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table(Vec::new())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Table<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct Visitor<T>(PhantomData<T>);
+        impl<'de, T: Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
+            type Value = Table<T>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a table")
+            }
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where A: de::MapAccess<'de>
+            {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Table(entries))
+            }
+        }
+        deserializer.deserialize_map(Visitor(PhantomData))
+    }
+}
+
+#[derive(Deserialize)]
+struct RawMeta {
+    kind: Spanned<String>,
+    ident: Spanned<String>,
+    module: Option<Spanned<String>>,
+    docs: String,
+    of: Option<Spanned<String>>,
+    include: Option<Spanned<String>>,
+    array: Option<Spanned<String>>,
+    derives: Option<Spanned<Vec<String>>>,
+}
+
+#[derive(Deserialize)]
+struct RawField {
+    ty: Spanned<String>,
+    of: Option<Spanned<toml::Value>>,
+    json: Option<Spanned<String>>,
+    docs: String,
+    optional: Option<Spanned<bool>>,
+    default: Option<Spanned<toml::Value>>,
+    minimum: Option<Spanned<toml::Value>>,
+    exclusive_minimum: Option<Spanned<toml::Value>>,
+    maximum: Option<Spanned<toml::Value>>,
+    min_items: Option<Spanned<i64>>,
+    max_items: Option<Spanned<i64>>,
+    unique_items: Option<Spanned<bool>>,
+    hidden: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RawVariant {
+    value: Spanned<toml::Value>,
+    docs: String,
+}
+
+#[derive(Deserialize)]
+struct RawUnion {
+    ident: Spanned<String>,
+    tag: Spanned<String>,
+    docs: String,
+}
+
+#[derive(Deserialize)]
+struct RawUnionVariant {
+    value: Spanned<String>,
+    of: Spanned<String>,
+    docs: String,
+}
+
+/// Methods of every wrapper, which fields cannot be named after.
+const WRAPPER_METHODS: &[&str] = &["index", "parent"];
+
+/// Converts raw definitions into typed ones, reporting errors against `file`.
+struct Validator<'a> {
+    file: &'a Path,
+    source: &'a str,
+}
+
+impl<'a> Validator<'a> {
+    /// Returns the one-based line and column of the start of `span`.
+    fn position(&self, span: (usize, usize)) -> (usize, usize) {
+        let before = &self.source[..span.0];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Returns an error for the entry at `key` spanning `span` in the source.
+    fn error<S>(&self, key: &str, span: (usize, usize), message: S) -> Error
+        where S: Into<String>
+    {
+        Error {
+            file: self.file.to_path_buf(),
+            key: Some(key.to_string()),
+            position: Some(self.position(span)),
+            message: message.into(),
+        }
+    }
+
+    fn definition(&self, raw: RawDefinition) -> Result<Definition, Error> {
+        if !is_ident(raw.meta.ident.get_ref()) {
+            let message = format!("`{}` is not a valid identifier", raw.meta.ident.get_ref());
+            return Err(self.error("meta.ident", raw.meta.ident.span(), message));
+        }
+        if let Some(ref module) = raw.meta.module {
+            if !is_type_path(module.get_ref()) {
+                let message = format!("`{}` is not a valid module path", module.get_ref());
+                return Err(self.error("meta.module", module.span(), message));
+            }
+        }
+        let kind = &raw.meta.kind;
+        if let Some(ref array) = raw.meta.array {
+            if kind.get_ref() != "Struct" && kind.get_ref() != "Union" {
+                let message = "`array` is only valid for struct and union definitions";
+                return Err(self.error("meta.array", array.span(), message));
+            }
+            if !is_ident(array.get_ref()) || *array.get_ref() != array.get_ref().to_camel_case() {
+                let message = format!("`{}` is not a valid camelCase array name", array.get_ref());
+                return Err(self.error("meta.array", array.span(), message));
+            }
+        }
+        if let (true, Some(module)) = (kind.get_ref() == "Root", &raw.meta.module) {
+            return Err(self.error("meta.module", module.span(), "the root definition cannot have a module"));
+        }
+        let include = match raw.meta.include {
+            Some(ref include) => {
+                let path = self.file.parent().unwrap_or_else(|| Path::new("")).join(include.get_ref());
+                if !path.is_file() {
+                    let message = format!("`{}` does not exist", path.display());
+                    return Err(self.error("meta.include", include.span(), message));
+                }
+                Some(path)
+            },
+            None => None,
+        };
+        let derives = match raw.meta.derives {
+            Some(derives) => {
+                let invalid = derives.get_ref().iter().find(|derive| syn::parse_str::<syn::Path>(derive).is_err());
+                if let Some(derive) = invalid {
+                    let message = format!("`{}` is not a valid derive path", derive);
+                    return Err(self.error("meta.derives", derives.span(), message));
+                }
+                derives.into_inner()
+            },
+            None => vec![],
+        };
+        let meta = Meta {
+            ident: raw.meta.ident.into_inner(),
+            module: raw.meta.module.map(Spanned::into_inner),
+            docs: raw.meta.docs,
+            include,
+            derives,
+            file: self.file.to_path_buf(),
+        };
+        match kind.get_ref().as_str() {
+            "Struct" => {
+                let fields = raw.fields.ok_or_else(|| {
+                    self.error("meta.kind", kind.span(), "struct definition has no `fields` table")
+                })?;
+                let mut def = self.struct_def(meta, fields)?;
+                def.array = raw.meta.array.map(Spanned::into_inner);
+                Ok(Definition::Struct(def))
+            },
+            "Union" => {
+                let union = raw.union.ok_or_else(|| {
+                    self.error("meta.kind", kind.span(), "union definition has no `union` table")
+                })?;
+                let variants = raw.variants.ok_or_else(|| {
+                    self.error("meta.kind", kind.span(), "union definition has no `variants` table")
+                })?;
+                let mut def = self.struct_def(meta, raw.fields.unwrap_or_default())?;
+                def.array = raw.meta.array.map(Spanned::into_inner);
+                def.union = Some(self.union_def(&def.fields, union, variants)?);
+                Ok(Definition::Struct(def))
+            },
+            "Root" => {
+                let def = self.struct_def(meta, raw.fields.unwrap_or_default())?;
+                Ok(Definition::Root(RootDef {
+                    meta: def.meta,
+                    fields: def.fields,
+                    collections: vec![],
+                    defaultable: false,
+                    hashable: false,
+                }))
+            },
+            "Enum" => {
+                let of = raw.meta.of.ok_or_else(|| {
+                    self.error("meta.kind", kind.span(), "enum definition has no `of` key")
+                })?;
+                let repr = match of.get_ref().as_str() {
+                    "Integer" => Repr::Integer,
+                    "String" => Repr::String,
+                    "Pattern" => Repr::Pattern,
+                    other => {
+                        let message = format!("unknown enum encoding `{}`, expected `Integer`, `String` or `Pattern`", other);
+                        return Err(self.error("meta.of", of.span(), message));
+                    },
+                };
+                let values = raw.values.ok_or_else(|| {
+                    self.error("meta.kind", kind.span(), "enum definition has no `values` table")
+                })?;
+                self.enum_def(meta, repr, values).map(Definition::Enum)
+            },
+            other => {
+                let message = format!("unknown definition kind `{}`, expected `Struct`, `Union`, `Enum`, or `Root`", other);
+                Err(self.error("meta.kind", kind.span(), message))
+            },
+        }
+    }
+
+    fn struct_def(&self, meta: Meta, raw_fields: Table<RawField>) -> Result<StructDef, Error> {
+        // JSON names already taken, including those of the boilerplate entries.
+        let mut json_names: HashMap<String, String> = ["name", "extras", "extensions"]
+            .iter()
+            .map(|name| (name.to_string(), name.to_string()))
+            .collect();
+        let mut fields = Vec::with_capacity(raw_fields.0.len());
+        for (ident, raw) in raw_fields.0 {
+            let key = format!("fields.{}", ident);
+            if !is_ident(&ident) {
+                let message = format!("`{}` is not a valid identifier", ident);
+                return Err(self.error(&key, raw.ty.span(), message));
+            }
+            if WRAPPER_METHODS.contains(&ident.as_str()) {
+                let message = format!("`{}` is reserved for the wrapper method of the same name", ident);
+                return Err(self.error(&key, raw.ty.span(), message));
+            }
+            let field = self.field_def(&key, ident, &raw)?;
+            if let Some(other) = json_names.insert(field.json.clone(), field.ident.clone()) {
+                let span = raw.json.as_ref().map_or(raw.ty.span(), Spanned::span);
+                let message = format!("JSON name `{}` is already used by field `{}`", field.json, other);
+                return Err(self.error(&format!("{}.json", key), span, message));
+            }
+            fields.push(field);
+        }
+        Ok(StructDef {
+            meta,
+            fields,
+            array: None,
+            union: None,
+            defaultable: false,
+            hashable: false,
+            parents: vec![],
+            scopes: BTreeMap::new(),
+        })
+    }
+
+    fn union_def(&self, fields: &[FieldDef], raw: RawUnion, raw_variants: Table<RawUnionVariant>) -> Result<UnionDef, Error> {
+        let ident = raw.ident.get_ref();
+        if !is_ident(ident) || *ident != ident.to_snake_case() {
+            let message = format!("`{}` is not a valid snake_case identifier", ident);
+            return Err(self.error("union.ident", raw.ident.span(), message));
+        }
+        let reserved = ["name", "extras", "extensions"];
+        if WRAPPER_METHODS.contains(&ident.as_str()) {
+            let message = format!("`{}` is reserved for the wrapper method of the same name", ident);
+            return Err(self.error("union.ident", raw.ident.span(), message));
+        }
+        if reserved.contains(&ident.as_str()) || fields.iter().any(|field| field.ident == *ident) {
+            let message = format!("identifier `{}` is already used by a field", ident);
+            return Err(self.error("union.ident", raw.ident.span(), message));
+        }
+        // JSON names already taken, including those of the boilerplate entries.
+        let mut json_names: HashMap<String, String> = reserved
+            .iter()
+            .map(|name| (name.to_string(), name.to_string()))
+            .chain(fields.iter().map(|field| (field.json.clone(), field.ident.clone())))
+            .map(|(json, ident)| (json, format!("field `{}`", ident)))
+            .collect();
+        if let Some(other) = json_names.insert(raw.tag.get_ref().clone(), "the discriminator".to_string()) {
+            let message = format!("JSON name `{}` is already used by {}", raw.tag.get_ref(), other);
+            return Err(self.error("union.tag", raw.tag.span(), message));
+        }
+        let mut variants = Vec::with_capacity(raw_variants.0.len());
+        for (ident, raw) in raw_variants.0 {
+            let key = format!("variants.{}", ident);
+            if !is_ident(&ident) {
+                let message = format!("`{}` is not a valid identifier", ident);
+                return Err(self.error(&key, raw.value.span(), message));
+            }
+            let value = raw.value.get_ref();
+            if value.is_empty() {
+                return Err(self.error(&format!("{}.value", key), raw.value.span(), "expected a non-empty string"));
+            }
+            if let Some(other) = json_names.insert(value.clone(), format!("variant `{}`", ident)) {
+                let message = format!("JSON name `{}` is already used by {}", value, other);
+                return Err(self.error(&format!("{}.value", key), raw.value.span(), message));
+            }
+            if !is_type_path(raw.of.get_ref()) {
+                let message = "union variants require a struct path";
+                return Err(self.error(&format!("{}.of", key), raw.of.span(), message));
+            }
+            variants.push(UnionVariantDef {
+                ident,
+                value: value.clone(),
+                path: raw.of.get_ref().clone(),
+                docs: raw.docs,
+                position: Some(self.position(raw.of.span())),
+            });
+        }
+        if variants.is_empty() {
+            return Err(Error {
+                file: self.file.to_path_buf(),
+                key: Some("variants".to_string()),
+                position: None,
+                message: "union definition has no variants".to_string(),
+            });
+        }
+        Ok(UnionDef { ident: raw.ident.into_inner(), tag: raw.tag.into_inner(), docs: raw.docs, variants })
+    }
+
+    fn field_def(&self, key: &str, ident: String, raw: &RawField) -> Result<FieldDef, Error> {
+        let ty = self.field_type(key, raw)?;
+        let optional = raw.optional.as_ref().is_some_and(|x| *x.get_ref());
+        if optional {
+            match ty {
+                Type::Bool | Type::Any | Type::Map(..) | Type::Special(_) => {
+                    let span = raw.optional.as_ref().unwrap().span();
+                    let message = format!("`{}` fields cannot be optional", ty.name());
+                    return Err(self.error(&format!("{}.optional", key), span, message));
+                },
+                // Wrappers return optional arrays by value, so the items must be `Copy`.
+                Type::FixedSizeArray(ref element, _) if !element.is_copy() => {
+                    let span = raw.optional.as_ref().unwrap().span();
+                    let message = format!("`FixedSizeArray` fields of `{}` items cannot be optional", element.name());
+                    return Err(self.error(&format!("{}.optional", key), span, message));
+                },
+                _ => {},
+            }
+        }
+        let default = match raw.default {
+            Some(ref value) => {
+                let key = format!("{}.default", key);
+                if optional {
+                    let message = "`default` and `optional` are mutually exclusive";
+                    return Err(self.error(&key, value.span(), message));
+                }
+                let element = ty.element();
+                if let (Type::Map(..), _) | (_, Type::Index(_) | Type::Struct(_) | Type::Number | Type::Any | Type::Special(_)) =
+                    (&ty, element)
+                {
+                    let message = format!("`{}` fields cannot have a default value", ty.name());
+                    return Err(self.error(&key, value.span(), message));
+                }
+                match default_literal(&ty, value.get_ref()) {
+                    Some(literal) => Some(literal),
+                    None => {
+                        let message = format!("default value is not a valid `{}`", ty.name());
+                        return Err(self.error(&key, value.span(), message));
+                    },
+                }
+            },
+            None => None,
+        };
+        let constraints = self.constraints(key, &ty, raw)?;
+        // Fall back to the glTF naming convention when `json` is absent.
+        let json = raw.json
+            .as_ref()
+            .map_or_else(|| ident.to_camel_case(), |json| json.get_ref().clone());
+        let position = Some(self.position(raw.of.as_ref().map_or(raw.ty.span(), Spanned::span)));
+        Ok(FieldDef {
+            ident,
+            ty,
+            json,
+            docs: raw.docs.clone(),
+            optional,
+            default,
+            constraints,
+            hidden: raw.hidden.unwrap_or(false),
+            scope: None,
+            position,
+        })
+    }
+
+    fn constraints(&self, key: &str, ty: &Type, raw: &RawField) -> Result<Constraints, Error> {
+        let bound = |name: &str, value: &Option<Spanned<toml::Value>>| {
+            let value = match *value {
+                Some(ref value) => value,
+                None => return Ok(None),
+            };
+            let key = format!("{}.{}", key, name);
+            let element = match *ty {
+                _ if ty.is_numeric() => ty,
+                Type::Array(ref element) | Type::FixedSizeArray(ref element, _) if element.is_numeric() => element,
+                _ => {
+                    let message = format!("`{}` is only valid for numbers and arrays of numbers", name);
+                    return Err(self.error(&key, value.span(), message));
+                },
+            };
+            match default_literal(element, value.get_ref()) {
+                Some(literal) => Ok(Some(literal)),
+                None => Err(self.error(&key, value.span(), format!("`{}` is not a valid `{}`", name, element.name()))),
+            }
+        };
+        let count = |name: &str, value: &Option<Spanned<i64>>| {
+            let value = match *value {
+                Some(ref value) => value,
+                None => return Ok(None),
+            };
+            let key = format!("{}.{}", key, name);
+            if !matches!(*ty, Type::Array(_)) {
+                return Err(self.error(&key, value.span(), format!("`{}` is only valid for `Array` fields", name)));
+            }
+            usize::try_from(*value.get_ref())
+                .map(Some)
+                .map_err(|_| self.error(&key, value.span(), "expected a non-negative integer"))
+        };
+        let constraints = Constraints {
+            minimum: bound("minimum", &raw.minimum)?,
+            exclusive_minimum: bound("exclusive_minimum", &raw.exclusive_minimum)?,
+            maximum: bound("maximum", &raw.maximum)?,
+            min_items: count("min_items", &raw.min_items)?,
+            max_items: count("max_items", &raw.max_items)?,
+            unique_items: raw.unique_items.as_ref().is_some_and(|x| *x.get_ref()),
+        };
+        if let (Some(_), Some(value)) = (&constraints.minimum, &raw.exclusive_minimum) {
+            let message = "`minimum` and `exclusive_minimum` are mutually exclusive";
+            return Err(self.error(&format!("{}.exclusive_minimum", key), value.span(), message));
+        }
+        if let (Some(min), Some(max), Some(value)) = (constraints.min_items, constraints.max_items, &raw.max_items) {
+            if min > max {
+                let message = "`max_items` is less than `min_items`";
+                return Err(self.error(&format!("{}.max_items", key), value.span(), message));
+            }
+        }
+        if let (true, Some(value)) = (constraints.unique_items, &raw.unique_items) {
+            // Items are compared with `PartialEq`, which structs may not implement.
+            if !matches!(*ty, Type::Array(ref element) if !matches!(*element.element(), Type::Struct(_))) {
+                let message = "`unique_items` is only valid for arrays without structs";
+                return Err(self.error(&format!("{}.unique_items", key), value.span(), message));
+            }
+        }
+        Ok(constraints)
+    }
+
+    fn field_type(&self, key: &str, raw: &RawField) -> Result<Type, Error> {
+        let ty = raw.ty.get_ref().as_str();
+        let of_key = format!("{}.of", key);
+        let of_path = || match raw.of {
+            Some(ref of) => match of.get_ref() {
+                toml::Value::String(path) if ty == "Special" && syn::parse_str::<syn::Type>(path).is_ok() => {
+                    Ok(path.clone())
+                },
+                toml::Value::String(path) if ty != "Special" && is_type_path(path) => Ok(path.clone()),
+                _ => Err(self.error(&of_key, of.span(), format!("`{}` fields require a type path", ty))),
+            },
+            None => Err(self.error(key, raw.ty.span(), format!("`{}` fields require an `of` key", ty))),
+        };
+        let scalar = |value| match raw.of {
+            Some(ref of) => {
+                let message = format!("`of` is not valid for `{}` fields", ty);
+                Err(self.error(&of_key, of.span(), message))
+            },
+            None => Ok(value),
+        };
+        if let Some(scalar_ty) = scalar_type(ty) {
+            return scalar(scalar_ty);
+        }
+        match ty {
+            "Any" => scalar(Type::Any),
+            "Index" => of_path().map(Type::Index),
+            "Struct" => of_path().map(Type::Struct),
+            "Enum" => of_path().map(Type::Enum),
+            "Special" => of_path().map(Type::Special),
+            "Array" => {
+                let of = raw.of.as_ref().ok_or_else(|| {
+                    self.error(key, raw.ty.span(), "`Array` fields require an `of` key")
+                })?;
+                element_type(of.get_ref()).map(|ty| Type::Array(Box::new(ty))).ok_or_else(|| {
+                    let message = "expected a scalar type, a struct path, or `{ ty = <type>, of = ... }`";
+                    self.error(&of_key, of.span(), message)
+                })
+            },
+            "FixedSizeArray" => {
+                let of = raw.of.as_ref().ok_or_else(|| {
+                    self.error(key, raw.ty.span(), "`FixedSizeArray` fields require an `of` key")
+                })?;
+                fixed_size_array_type(of.get_ref()).ok_or_else(|| {
+                    let message = "expected `{ ty = <scalar type>, n = <length> }` or \
+                                   `{ ty = <type>, of = ..., n = <length> }`";
+                    self.error(&of_key, of.span(), message)
+                })
+            },
+            "Map" => {
+                let of = raw.of.as_ref().ok_or_else(|| {
+                    self.error(key, raw.ty.span(), "`Map` fields require an `of` key")
+                })?;
+                map_type(of.get_ref()).ok_or_else(|| {
+                    let message = "expected `{ key = \"String\" | <enum path> | { ty = \"Special\", of = ... }, \
+                                   value = <scalar type> | { ty = <type>, of = ... } }`";
+                    self.error(&of_key, of.span(), message)
+                })
+            },
+            other => {
+                let message = format!(
+                    "unknown field type `{}`, expected one of: Integer, Integer64, SignedInteger, Float, Double, \
+                     Number, Bool, String, Any, Index, Struct, Enum, Array, FixedSizeArray, Map, Special",
+                    other,
+                );
+                Err(self.error(&format!("{}.ty", key), raw.ty.span(), message))
+            },
+        }
+    }
+
+    fn enum_def(&self, meta: Meta, repr: Repr, raw_variants: Table<RawVariant>) -> Result<EnumDef, Error> {
+        let mut values = HashMap::new();
+        let mut variants = Vec::with_capacity(raw_variants.0.len());
+        for (ident, raw) in raw_variants.0 {
+            let key = format!("values.{}.value", ident);
+            if !is_ident(&ident) {
+                let message = format!("`{}` is not a valid identifier", ident);
+                return Err(self.error(&format!("values.{}", ident), raw.value.span(), message));
+            }
+            let (value, payload) = match (repr, raw.value.get_ref()) {
+                (Repr::Integer, toml::Value::Integer(x)) if *x >= 0 && *x <= i64::from(u32::MAX) => {
+                    (Literal::Integer(*x), None)
+                },
+                (Repr::String, toml::Value::String(x)) => (Literal::String(x.clone()), None),
+                (Repr::Pattern, toml::Value::String(x)) => match pattern(x) {
+                    Some((prefix, payload)) => (Literal::String(prefix.to_string()), payload),
+                    None => {
+                        let message = "expected a string, optionally ending in `{u32}` or `{String}` after a prefix";
+                        return Err(self.error(&key, raw.value.span(), message));
+                    },
+                },
+                (Repr::Integer, _) => {
+                    return Err(self.error(&key, raw.value.span(), "expected an integer between 0 and 2^32 - 1"));
+                },
+                (Repr::String, _) | (Repr::Pattern, _) => {
+                    return Err(self.error(&key, raw.value.span(), "expected a string"));
+                },
+            };
+            let raw_value = raw.value.get_ref().to_string();
+            if let Some(other) = values.insert(raw_value.clone(), ident.clone()) {
+                let message = format!("value {} is already used by variant `{}`", raw_value, other);
+                return Err(self.error(&key, raw.value.span(), message));
+            }
+            if payload.is_some() {
+                // Patterns are tried in order, so a prefix of another would
+                // shadow it or make parsing ambiguous.
+                let overlap = variants.iter().find(|other: &&VariantDef| match (&other.value, &value) {
+                    (Literal::String(a), Literal::String(b)) => {
+                        other.payload.is_some() && (a.starts_with(b.as_str()) || b.starts_with(a.as_str()))
+                    },
+                    _ => false,
+                });
+                if let Some(other) = overlap {
+                    let message = format!("pattern overlaps the pattern of variant `{}`", other.ident);
+                    return Err(self.error(&key, raw.value.span(), message));
+                }
+            }
+            variants.push(VariantDef { ident, value, payload, docs: raw.docs });
+        }
+        if variants.is_empty() {
+            return Err(Error {
+                file: self.file.to_path_buf(),
+                key: Some("values".to_string()),
+                position: None,
+                message: "enum definition has no variants".to_string(),
+            });
+        }
+        Ok(EnumDef { meta, repr, variants })
+    }
+}
+
+/// Splits a pattern enum value into its prefix and payload, if valid.
+///
+/// Values without a placeholder are literals. Placeholders must follow a
+/// non-empty prefix, e.g. `"COLOR_{u32}"` or `"_{String}"`.
+fn pattern(value: &str) -> Option<(&str, Option<Payload>)> {
+    let (prefix, payload) = if let Some(prefix) = value.strip_suffix("{u32}") {
+        (prefix, Some(Payload::Integer))
+    } else if let Some(prefix) = value.strip_suffix("{String}") {
+        (prefix, Some(Payload::String))
+    } else {
+        (value, None)
+    };
+    if prefix.contains(['{', '}']) || (payload.is_some() && prefix.is_empty()) {
+        None
+    } else {
+        Some((prefix, payload))
+    }
+}
+
+/// Converts the name of a scalar field type, if valid.
+fn scalar_type(name: &str) -> Option<Type> {
+    match name {
+        "Integer" => Some(Type::Integer),
+        "Integer64" => Some(Type::Integer64),
+        "SignedInteger" => Some(Type::SignedInteger),
+        "Float" => Some(Type::Float),
+        "Double" => Some(Type::Double),
+        "Number" => Some(Type::Number),
+        "Bool" => Some(Type::Bool),
+        "String" => Some(Type::String),
+        _ => None,
+    }
+}
+
+/// Converts the element type of an `Array` or `FixedSizeArray` field, or the
+/// value type of a `Map` field, if valid.
+///
+/// Elements are scalars, given by name, or structs, given by path, or
+/// `{ ty = ..., of = ... }` tables, where `of` is given as for a field of type
+/// `ty`, so arrays and maps nest.
+fn element_type(value: &toml::Value) -> Option<Type> {
+    match value {
+        toml::Value::String(ref name) => match scalar_type(name) {
+            Some(ty) => Some(ty),
+            None if is_type_path(name) => Some(Type::Struct(name.clone())),
+            None => None,
+        },
+        toml::Value::Table(ref table) => {
+            let ty = table.get("ty").and_then(toml::Value::as_str)?;
+            match (ty, table.get("of")) {
+                ("Array", Some(of)) => element_type(of).map(|ty| Type::Array(Box::new(ty))),
+                ("FixedSizeArray", Some(of)) => fixed_size_array_type(of),
+                ("Map", Some(of)) => map_type(of),
+                (_, Some(toml::Value::String(path))) if is_type_path(path) => match ty {
+                    "Index" => Some(Type::Index(path.clone())),
+                    "Enum" => Some(Type::Enum(path.clone())),
+                    "Struct" => Some(Type::Struct(path.clone())),
+                    _ => None,
+                },
+                (_, Some(_)) => None,
+                (_, None) => scalar_type(ty),
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Converts the `of` table of a `FixedSizeArray`, i.e. an element as for
+/// `Array` given as a table, plus its length `n`.
+fn fixed_size_array_type(of: &toml::Value) -> Option<Type> {
+    let mut table = of.as_table().cloned()?;
+    let n = table.remove("n").as_ref().and_then(toml::Value::as_integer).filter(|n| *n > 0)?;
+    let element = element_type(&toml::Value::Table(table))?;
+    Some(Type::FixedSizeArray(Box::new(element), n as usize))
+}
+
+/// Converts the `of` table of a `Map`.
+///
+/// Values may be any element type that does not contain a struct.
+fn map_type(of: &toml::Value) -> Option<Type> {
+    let table = of.as_table()?;
+    let key = match table.get("key")? {
+        toml::Value::String(name) if name == "String" => Type::String,
+        toml::Value::String(path) if is_type_path(path) => Type::Enum(path.clone()),
+        toml::Value::Table(table) => match (table.get("ty"), table.get("of")) {
+            (Some(toml::Value::String(ty)), Some(toml::Value::String(path)))
+                if ty == "Special" && syn::parse_str::<syn::Type>(path).is_ok() =>
+            {
+                Type::Special(path.clone())
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let value = table.get("value").and_then(element_type).filter(|ty| !matches!(*ty.element(), Type::Struct(_)))?;
+    Some(Type::Map(Box::new(key), Box::new(value)))
+}
+
+/// Converts the default `value` of a field of type `ty`, if valid.
+///
+/// Enum variants are given by identifier and resolved when the schema is
+/// linked.
+fn default_literal(ty: &Type, value: &toml::Value) -> Option<Literal> {
+    match (ty, value) {
+        (Type::Integer, toml::Value::Integer(x)) if *x >= 0 && *x <= i64::from(u32::MAX) => Some(Literal::Integer(*x)),
+        (Type::Integer64, toml::Value::Integer(x)) if *x >= 0 => Some(Literal::Integer(*x)),
+        (Type::SignedInteger, toml::Value::Integer(x)) if i32::try_from(*x).is_ok() => Some(Literal::Integer(*x)),
+        (Type::Float, toml::Value::Float(x)) | (Type::Double, toml::Value::Float(x)) => Some(Literal::Float(*x)),
+        (Type::Float, toml::Value::Integer(x)) | (Type::Double, toml::Value::Integer(x)) => Some(Literal::Float(*x as f64)),
+        (Type::Bool, toml::Value::Boolean(x)) => Some(Literal::Bool(*x)),
+        (Type::String, toml::Value::String(x)) => Some(Literal::String(x.clone())),
+        (Type::Enum(_), toml::Value::String(x)) if is_ident(x) => Some(Literal::Variant(x.clone())),
+        (Type::FixedSizeArray(_, n), toml::Value::Array(values)) if values.len() != *n => None,
+        (Type::Array(element), toml::Value::Array(values)) | (Type::FixedSizeArray(element, _), toml::Value::Array(values)) => {
+            values.iter().map(|value| default_literal(element, value)).collect::<Option<_>>().map(Literal::Array)
+        },
+        _ => None,
+    }
+}
+
+/// Returns `true` if `name` is a Rust identifier other than a keyword.
+pub(crate) fn is_ident(name: &str) -> bool {
+    syn::parse_str::<syn::Ident>(name).is_ok()
+}
+
+/// Returns `true` if `path` is a `::` separated sequence of identifiers.
+fn is_type_path(path: &str) -> bool {
+    path.split("::").all(is_ident)
+}
+
+/// Converts an error parsing the TOML file at `path`, moving the position out
+/// of the message.
+pub(crate) fn toml_error(path: &Path, err: &toml::de::Error) -> Error {
+    let mut message = err.to_string();
+    let position = err.line_col().map(|(line, column)| (line + 1, column + 1));
+    if let Some((line, column)) = position {
+        let suffix = format!(" at line {} column {}", line, column);
+        if message.ends_with(&suffix) {
+            message.truncate(message.len() - suffix.len());
+        }
+    }
+    Error { file: path.to_path_buf(), key: None, position, message }
+}
+
+/// Loads and validates the schema definition at `path`.
+pub fn load(path: &Path) -> Result<Definition, Error> {
+    let source = fs::read_to_string(path).map_err(|err| Error {
+        file: path.to_path_buf(),
+        key: None,
+        position: None,
+        message: err.to_string(),
+    })?;
+    let raw: RawDefinition = toml::from_str(&source).map_err(|err| toml_error(path, &err))?;
+    let validator = Validator { file: path, source: &source };
+    validator.definition(raw)
+}
@@ -0,0 +1,27 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn generates_module_tree() {
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("batch");
+    let _ = fs::remove_dir_all(&out_dir);
+    let status = Command::new(env!("CARGO_BIN_EXE_gltf-codegen-toml"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .arg("schema")
+        .arg(&out_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let lib = fs::read_to_string(out_dir.join("lib.rs")).unwrap();
+    assert!(lib.contains("pub mod json;"));
+    assert!(lib.contains("pub mod mesh;"));
+    assert!(!lib.contains("pub mod templates;"));
+
+    // Both definitions in `mesh/` end up in the same module.
+    let mesh = fs::read_to_string(out_dir.join("json").join("mesh.rs")).unwrap();
+    assert!(mesh.contains("pub struct Mesh {"));
+    assert!(mesh.contains("pub struct Primitive {"));
+    assert_eq!(1, mesh.matches("pub enum Mode {").count());
+}
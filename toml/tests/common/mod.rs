@@ -0,0 +1,16 @@
+//! Helpers shared by the integration tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes `files`, pairs of file name and contents, to a fresh directory
+/// `name` under the target directory, and returns its path.
+pub fn write_schema(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, source) in files {
+        fs::write(dir.join(file), source).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,319 @@
+use gltf_codegen_toml::Generator;
+use quote::{quote, ToTokens};
+
+#[test]
+fn generates_enabled_modules_inline() {
+    let source = Generator::new("schema")
+        .modules(["accessor", "buffer"])
+        .struct_derives(["Clone", "Debug", "Deserialize", "Serialize"])
+        .names_feature(None)
+        .extras_feature(Some("gltf_extras"))
+        .generate()
+        .unwrap();
+    assert!(source.starts_with("// Generated by gltf-codegen-toml. Do not edit."));
+    assert!(source.contains("pub mod json {"), "{}", source);
+    assert!(source.contains("pub mod accessor {"), "{}", source);
+    assert!(source.contains("pub mod buffer {"), "{}", source);
+    assert!(!source.contains("pub mod mesh {"), "{}", source);
+    assert!(source.contains("::gltf_codegen_runtime::serde::Deserialize,"), "{}", source);
+    let file = syn::parse_file(&source).unwrap();
+    let accessor = module_items(&file.items, &["json", "accessor"]);
+    let mut derives = attributes(accessor, "Accessor").into_iter().filter(|attr| attr.starts_with("#[derive("));
+    assert!(!derives.any(|attr| attr.contains("Hash")));
+    assert!(source.contains("#[cfg(feature = \"gltf_extras\")]"), "{}", source);
+    assert!(!source.contains("feature = \"names\""), "{}", source);
+}
+
+#[test]
+fn reports_references_into_disabled_modules() {
+    let err = Generator::new("schema").modules(["accessor"]).generate().unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("`Index` refers to `buffer::View`, whose module is not enabled"), "{}", message);
+}
+
+#[test]
+fn rejects_invalid_derives() {
+    let err = Generator::new("schema").enum_derives(["Clone", "not a path"]).generate().unwrap_err();
+    assert_eq!(err.to_string(), "invalid derive `not a path`");
+}
+
+#[test]
+fn refers_to_the_runtime_crate() {
+    let source = Generator::new("schema")
+        .modules(["accessor", "buffer"])
+        .runtime_crate("::gltf::runtime")
+        .generate()
+        .unwrap();
+    assert!(source.contains("#[serde(crate = \"::gltf::runtime::serde\")]"), "{}", source);
+    assert!(source.contains("pub buffer_view: ::gltf::runtime::Index<crate::json::buffer::View>,"), "{}", source);
+    assert!(source.contains("impl ::gltf::runtime::validation::Enum for DataType {"), "{}", source);
+}
+
+#[test]
+fn generates_document_from_array_definitions() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("pub struct Document {"), "{}", source);
+    assert!(source.contains("pub fn buffer_views(&self) -> BufferViews<'_> {"), "{}", source);
+    assert!(source.contains("impl ::gltf_codegen_runtime::Get<crate::json::buffer::View> for Root {"), "{}", source);
+    assert!(source.contains("pub fn default_scene(&self) -> Option<crate::scene::Scene<'_>> {"), "{}", source);
+
+    let source = Generator::new("schema").modules(["accessor", "buffer"]).generate().unwrap();
+    assert!(source.contains("pub fn accessors(&self) -> Accessors<'_> {"), "{}", source);
+    assert!(!source.contains("pub fn meshes(&self)"), "{}", source);
+    assert!(!source.contains("default_scene"), "{}", source);
+}
+
+#[test]
+fn uses_gl_values_as_discriminants() {
+    let source = Generator::new("schema").modules(["accessor", "buffer"]).generate().unwrap();
+    let file = syn::parse_file(&source).unwrap();
+    let accessor = module_items(&file.items, &["json", "accessor"]);
+    assert!(attributes(accessor, "DataType").contains(&tokens(&quote!(#[repr(u32)]))));
+    assert!(source.contains("F32 = 5126,"), "{}", source);
+    assert!(source.contains("impl ::std::convert::TryFrom<u32> for DataType {"), "{}", source);
+    assert!(source.contains("impl ::std::str::FromStr for Dimensions {"), "{}", source);
+    assert!(source.contains("impl ::std::fmt::Display for Dimensions {"), "{}", source);
+}
+
+#[test]
+fn implements_default_from_field_defaults() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("impl Default for Node {"), "{}", source);
+    assert!(source.contains("scale: Node::scale_default(),"), "{}", source);
+    assert!(source.contains("impl Default for Root {"), "{}", source);
+    assert!(!source.contains("impl Default for Accessor {"), "{}", source);
+    assert!(source.contains("fn wrap_s_default() -> ::gltf_codegen_runtime::validation::Checked<"), "{}", source);
+}
+
+#[test]
+fn derives_eq_and_hash_only_without_floats() {
+    let source = Generator::new("schema").generate().unwrap();
+    let derives = |ident: &str| {
+        let end = source.find(&format!("pub struct {} {{", ident)).unwrap();
+        let start = source[..end].rfind("#[derive(").unwrap();
+        source[start..end].split(|c: char| !c.is_alphanumeric()).map(str::to_string).collect::<Vec<_>>()
+    };
+    let eq = ["Eq".to_string(), "Hash".to_string()];
+    assert!(eq.iter().all(|name| derives("Buffer").contains(name)));
+    assert!(!eq.iter().any(|name| derives("Perspective").contains(name)));
+    // `Camera` has no floats of its own, but holds a `Perspective`.
+    assert!(!eq.iter().any(|name| derives("Camera").contains(name)));
+    assert!(!eq.iter().any(|name| derives("Node").contains(name)));
+    assert!(!eq.iter().any(|name| derives("Root").contains(name)));
+}
+
+#[test]
+fn generates_map_iterators() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("pub attributes: ::gltf_codegen_runtime::Map<"), "{}", source);
+    assert!(source.contains("pub fn attributes(&self) -> Attributes<'a> {"), "{}", source);
+    let file = syn::parse_file(&source).unwrap();
+    let mesh = module_items(&file.items, &["mesh"]);
+    let item = impl_items(mesh, Some("Iterator"), "Attributes").into_iter().find_map(|item| match item {
+        syn::ImplItem::Type(item) if item.ident == "Item" => Some(&item.ty),
+        _ => None,
+    });
+    let elements = match item {
+        Some(syn::Type::Tuple(tuple)) => tuple.elems.iter().map(tokens).collect::<Vec<_>>(),
+        item => panic!("expected a tuple, found {:?}", item.map(tokens)),
+    };
+    let expected = [
+        tokens(&quote!(::gltf_codegen_runtime::validation::Checked<crate::json::mesh::Semantic>)),
+        tokens(&quote!(Option<crate::accessor::Accessor<'a>>)),
+    ];
+    assert_eq!(elements, expected);
+    let get = signature(mesh, "Attributes", "get");
+    let inputs = get.inputs.iter().map(tokens).collect::<Vec<_>>();
+    assert_eq!(inputs, [tokens(&quote!(&self)), tokens(&quote!(key: &crate::json::mesh::Semantic))]);
+}
+
+#[test]
+fn maps_wide_scalars_to_rust_types() {
+    let source = Generator::new("schema").modules(["accessor", "buffer"]).generate().unwrap();
+    assert!(source.contains("pub byte_length: u64,"), "{}", source);
+    assert!(source.contains("pub min: Vec<f64>,"), "{}", source);
+    assert!(source.contains("pub type Min<'a> = ::std::slice::Iter<'a, f64>;"), "{}", source);
+}
+
+#[test]
+fn composes_nested_element_types() {
+    let source = Generator::new("schema").modules(["accessor", "buffer", "image", "material", "mesh", "texture"]).generate().unwrap();
+    assert!(source.contains("pub targets: Option<"), "{}", source);
+    assert!(source.contains("pub fn targets(&self) -> Option<Targets<'a>> {"), "{}", source);
+    assert!(source.contains("if let Some(ref items) = self.targets {"), "{}", source);
+}
+
+#[test]
+fn generates_editing_wrappers() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("pub struct NodeMut<'a> {"), "{}", source);
+    assert!(source.contains("pub fn push_to_children("), "{}", source);
+    assert!(source.contains("pub fn set_translation(&mut self, translation: [f32; 3]) -> &mut Self {"), "{}", source);
+    assert!(source.contains("pub fn push_to_meshes("), "{}", source);
+
+    // Channels check their sampler against the samplers of their animation.
+    assert!(source.contains("let samplers_len = self.json.samplers.len();"), "{}", source);
+    assert!(source.contains("sampler.check(self.samplers_len)?;"), "{}", source);
+}
+
+#[test]
+fn stores_only_what_wrappers_read() {
+    let source = Generator::new("schema").generate().unwrap();
+    let file = syn::parse_file(&source).unwrap();
+    let asset = module_items(&file.items, &["asset"]);
+    assert_eq!(field_names(asset, "Asset"), ["index", "json"]);
+    assert_eq!(field_names(asset, "AssetMut"), ["json"]);
+    assert!(!method_names(asset, "Asset").contains(&"new".to_string()));
+    assert!(!method_names(asset, "AssetRef").contains(&"new".to_string()));
+    let scene = module_items(&file.items, &["scene"]);
+    assert_eq!(field_names(scene, "Node"), ["document", "index", "json"]);
+    assert_eq!(field_names(scene, "NodeMut"), ["json", "lengths"]);
+    assert!(method_names(scene, "Node").contains(&"new".to_string()));
+}
+
+#[test]
+fn generates_owned_handles() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("pub struct NodeRef {"), "{}", source);
+    assert!(source.contains("pub fn children(&self) -> crate::Handles<Option<crate::scene::NodeRef>> {"), "{}", source);
+    assert!(source.contains("pub fn nodes_ref(self: &::std::sync::Arc<Self>) -> Handles<crate::scene::NodeRef> {"), "{}", source);
+    assert!(source.contains("impl crate::WrapRef for crate::json::scene::Node {"), "{}", source);
+}
+
+#[test]
+fn generates_builders() {
+    let source = Generator::new("schema").generate().unwrap();
+    assert!(source.contains("pub struct ViewBuilder {"), "{}", source);
+    let file = syn::parse_file(&source).unwrap();
+    let buffer = module_items(&file.items, &["buffer"]);
+    let setter = signature(buffer, "ViewBuilder", "buffer");
+    let inputs = setter.inputs.iter().map(tokens).collect::<Vec<_>>();
+    let param = quote!(buffer: ::gltf_codegen_runtime::Index<crate::json::buffer::Buffer>);
+    assert_eq!(inputs, [tokens(&quote!(&mut self)), tokens(&param)]);
+    assert_eq!(tokens(&setter.output), tokens(&quote!(-> &mut Self)));
+    assert!(source.contains(".unwrap_or_else(crate::json::buffer::View::byte_offset_default)"), "{}", source);
+    assert!(source.contains("pub struct DocumentBuilder {"), "{}", source);
+    assert!(source.contains("item.check_indices(lengths, self.samplers.len())?;"), "{}", source);
+    assert!(source.contains("pub fn build(&mut self) -> Result<Document, ::gltf_codegen_runtime::BuildError> {"), "{}", source);
+}
+
+// The generated code is compiled against the runtime crate by its tests.
+#[test]
+fn returns_bool_from_default_checks() {
+    fn is_default_fns(items: &[syn::Item], found: &mut Vec<syn::ImplItemFn>) {
+        for item in items {
+            match item {
+                syn::Item::Mod(module) => is_default_fns(&module.content.as_ref().unwrap().1, found),
+                syn::Item::Impl(block) => found.extend(block.items.iter().filter_map(|item| match item {
+                    syn::ImplItem::Fn(function) if function.sig.ident.to_string().ends_with("_is_default") => Some(function.clone()),
+                    _ => None,
+                })),
+                _ => {},
+            }
+        }
+    }
+
+    let source = Generator::new("schema").generate().unwrap();
+    let file = syn::parse_file(&source).unwrap();
+
+    // The checks skipping default values return `bool`, whatever the field type.
+    let mut found = vec![];
+    is_default_fns(&file.items, &mut found);
+    assert!(found.iter().any(|function| function.sig.ident == "byte_offset_is_default"));
+    for function in &found {
+        let output = match function.sig.output {
+            syn::ReturnType::Type(_, ref ty) => quote!(#ty).to_string(),
+            syn::ReturnType::Default => String::new(),
+        };
+        assert_eq!(output, "bool", "{}", function.sig.ident);
+    }
+}
+
+/// Returns the items of the module at `path` within `items`.
+fn module_items<'a>(items: &'a [syn::Item], path: &[&str]) -> &'a [syn::Item] {
+    path.iter().fold(items, |items, name| {
+        items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Mod(module) if module.ident == name => Some(&module.content.as_ref().unwrap().1[..]),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no module `{}`", name))
+    })
+}
+
+/// Returns the struct or enum `name` within `items`.
+fn find_item<'a>(items: &'a [syn::Item], name: &str) -> &'a syn::Item {
+    items
+        .iter()
+        .find(|item| match item {
+            syn::Item::Struct(item) => item.ident == name,
+            syn::Item::Enum(item) => item.ident == name,
+            _ => false,
+        })
+        .unwrap_or_else(|| panic!("no struct or enum `{}`", name))
+}
+
+/// Returns the attributes of the struct or enum `name` within `items`, as
+/// strings of `tokens`.
+fn attributes(items: &[syn::Item], name: &str) -> Vec<String> {
+    let attrs = match find_item(items, name) {
+        syn::Item::Struct(item) => &item.attrs,
+        syn::Item::Enum(item) => &item.attrs,
+        _ => unreachable!(),
+    };
+    attrs.iter().map(tokens).collect()
+}
+
+/// Returns the field names of the struct `name` within `items`.
+fn field_names(items: &[syn::Item], name: &str) -> Vec<String> {
+    match find_item(items, name) {
+        syn::Item::Struct(item) => item.fields.iter().map(|field| field.ident.as_ref().unwrap().to_string()).collect(),
+        _ => panic!("`{}` is not a struct", name),
+    }
+}
+
+/// Returns the items of the impls of `trait_`, or the inherent impls for
+/// `None`, for the type `name` within `items`.
+fn impl_items<'a>(items: &'a [syn::Item], trait_: Option<&str>, name: &str) -> Vec<&'a syn::ImplItem> {
+    let last = |path: &syn::Path| path.segments.last().unwrap().ident.to_string();
+    items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Impl(block) => Some(block),
+            _ => None,
+        })
+        .filter(|block| block.trait_.as_ref().map(|(_, path, _)| last(path)).as_deref() == trait_)
+        .filter(|block| matches!(&*block.self_ty, syn::Type::Path(ty) if last(&ty.path) == name))
+        .flat_map(|block| &block.items)
+        .collect()
+}
+
+/// Returns the names of the methods in the inherent impls of `name` within
+/// `items`.
+fn method_names(items: &[syn::Item], name: &str) -> Vec<String> {
+    impl_items(items, None, name)
+        .into_iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(function) => Some(function.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the signature of the method `method` in the inherent impls of
+/// `name` within `items`.
+fn signature<'a>(items: &'a [syn::Item], name: &str, method: &str) -> &'a syn::Signature {
+    impl_items(items, None, name)
+        .into_iter()
+        .find_map(|item| match item {
+            syn::ImplItem::Fn(function) if function.sig.ident == method => Some(&function.sig),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no method `{}::{}`", name, method))
+}
+
+/// Returns the tokens of `node` as a string without whitespace.
+fn tokens<T: ToTokens>(node: &T) -> String {
+    node.to_token_stream().to_string().replace(' ', "")
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use gltf_codegen_toml::schema::{Error, Errors};
+use gltf_codegen_toml::Generator;
+use std::path::Path;
+
+/// Links the schema in `dir`, returning the errors.
+fn link_errors(dir: &Path) -> Vec<Error> {
+    let err = Generator::new(dir).generate().unwrap_err();
+    err.downcast::<Errors>().expect("link errors").0
+}
+
+/// Returns the error at `key` of the schema file `file`.
+fn find<'a>(errors: &'a [Error], file: &str, key: &str) -> &'a Error {
+    errors
+        .iter()
+        .find(|error| error.file.file_name() == Some(file.as_ref()) && error.key.as_deref() == Some(key))
+        .unwrap_or_else(|| panic!("no error at `{}` of {}: {:#?}", key, file, errors))
+}
+
+#[test]
+fn reports_unresolved_references() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Info"
+module = "texture"
+docs = "Reference to a texture."
+
+[fields.texture]
+ty = "Index"
+of = "texture::Texture"
+json = "index"
+docs = "The referenced texture."
+
+[fields.tex_coord]
+ty = "Index"
+of = "Integer"
+docs = "The set index of the texture's `TEXCOORD` attribute."
+
+[fields.scale]
+ty = "Struct"
+of = "Double"
+docs = "A scalar multiplier."
+"#;
+    let errors = link_errors(&common::write_schema("link", &[("Info.toml", source)]));
+    let error = find(&errors, "Info.toml", "fields.texture.of");
+    assert_eq!(error.position, Some((9, 6)));
+    assert_eq!(error.message, "`Index` refers to `texture::Texture`, which is not defined");
+    let error = find(&errors, "Info.toml", "fields.tex_coord.of");
+    assert_eq!(error.position, Some((15, 6)));
+    assert_eq!(error.message, "`Index` refers to `Integer`, which is a scalar type, not a struct");
+    let error = find(&errors, "Info.toml", "fields.scale.of");
+    assert_eq!(error.position, Some((20, 6)));
+    assert_eq!(error.message, "`Struct` refers to `Double`, which is a scalar type, not a struct");
+}
+
+#[test]
+fn reports_duplicate_arrays() {
+    let source = |ident: &str| {
+        format!(
+            "[meta]\nkind = \"Struct\"\nident = \"{}\"\nmodule = \"image\"\ndocs = \"An image.\"\narray = \"images\"\n\n[fields]\n",
+            ident,
+        )
+    };
+    let files = [("Image.toml", &source("Image")[..]), ("Picture.toml", &source("Picture")[..])];
+    let errors = link_errors(&common::write_schema("link-arrays", &files));
+    let error = find(&errors, "Picture.toml", "meta.array");
+    assert_eq!(error.message, "array `images` is already used by `image::Image`");
+}
+
+#[test]
+fn reports_unknown_default_variants() {
+    let source = r#"[meta]
+kind = "Enum"
+of = "Integer"
+ident = "Mode"
+module = "mesh"
+docs = "The type of primitives to render."
+
+[values]
+Points = { value = 0, docs = "Corresponds to `GL_POINTS`." }
+"#;
+    let primitive = r#"[meta]
+kind = "Struct"
+ident = "Primitive"
+module = "mesh"
+docs = "Geometry to be rendered."
+
+[fields.mode]
+ty = "Enum"
+of = "mesh::Mode"
+docs = "The type of primitives to render."
+default = "Triangles"
+"#;
+    let errors = link_errors(&common::write_schema("link-defaults", &[("Mode.toml", source), ("Primitive.toml", primitive)]));
+    let error = find(&errors, "Primitive.toml", "fields.mode.default");
+    assert_eq!(error.message, "`Triangles` is not a variant of `mesh::Mode`");
+}
+
+#[test]
+fn reports_unresolved_union_variants() {
+    let source = r#"[meta]
+kind = "Union"
+ident = "Light"
+module = "light"
+docs = "A light source."
+
+[union]
+ident = "kind"
+tag = "type"
+docs = "The kind of light."
+
+[variants.Spot]
+value = "spot"
+of = "light::Spot"
+docs = "A spot light."
+"#;
+    let errors = link_errors(&common::write_schema("link-unions", &[("Light.toml", source)]));
+    let error = find(&errors, "Light.toml", "variants.Spot.of");
+    assert_eq!(error.position, Some((14, 6)));
+    assert_eq!(error.message, "`Struct` refers to `light::Spot`, which is not defined");
+}
+
+#[test]
+fn reports_unscoped_local_indices() {
+    let sampler = r#"[meta]
+kind = "Struct"
+ident = "Sampler"
+module = "animation"
+docs = "Combines input and output accessors with an interpolation algorithm."
+
+[fields.input]
+ty = "Integer"
+json = "input"
+docs = "The index of an accessor containing keyframe input values."
+"#;
+    let channel = r#"[meta]
+kind = "Struct"
+ident = "Channel"
+module = "animation"
+docs = "Targets an animation's sampler at a node's property."
+
+[fields.sampler]
+ty = "Index"
+of = "animation::Sampler"
+json = "sampler"
+docs = "The sampler of the animation used to compute the value for the target."
+"#;
+    let errors = link_errors(&common::write_schema("link-scopes", &[("Sampler.toml", sampler), ("Channel.toml", channel)]));
+    let error = find(&errors, "Channel.toml", "fields.sampler.of");
+    assert_eq!(error.position, Some((9, 6)));
+    let expected = "`animation::Sampler` is neither in a top-level array nor in a single array of the one parent of `animation::Channel`";
+    assert_eq!(error.message, expected);
+}
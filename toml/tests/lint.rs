@@ -0,0 +1,97 @@
+mod common;
+
+#[test]
+fn accepts_the_gltf_schema() {
+    let errors = gltf_codegen_toml::lint::lint("schema");
+    assert!(errors.is_empty(), "{:#?}", errors);
+}
+
+#[test]
+fn reports_unknown_keys_and_conventions() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "sloppy"
+module = "misc"
+docs = "A definition breaking the conventions."
+notes = "Not read by the generator."
+
+[fields.Count]
+ty = "Integer"
+docs = "A count."
+
+[fields.total]
+ty = "Integer"
+json = "count"
+docs = ""
+"#;
+    let path = common::write_schema("sloppy", &[("Sloppy.toml", source)]).join("Sloppy.toml");
+    let errors = gltf_codegen_toml::lint::lint(&path);
+    let found = errors
+        .iter()
+        .map(|error| (error.position, error.key.as_deref().unwrap(), error.message.as_str()))
+        .collect::<Vec<_>>();
+    let expected = [
+        (Some((3, 1)), "meta.ident", "`sloppy` is not PascalCase"),
+        (Some((6, 1)), "meta.notes", "unknown key `notes`"),
+        (Some((8, 1)), "fields.Count", "field name `Count` is not snake_case"),
+        (Some((8, 1)), "fields.Count", "missing `json`"),
+        (Some((14, 1)), "fields.total.json", "JSON name `count` is already used by field `Count`"),
+        (Some((15, 1)), "fields.total.docs", "empty `docs`"),
+    ];
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn reports_typos_and_template_violations() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Typo"
+docs = "A misspelt boolean."
+
+[fields.count]
+ty = "Integer"
+json = "count"
+docs = "A count."
+optional = ture
+"#;
+    let path = common::write_schema("typo", &[("Typo.toml", source)]).join("Typo.toml");
+    let errors = gltf_codegen_toml::lint::lint(&path);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].position, Some((10, 12)));
+
+    // Files that pass the lint are checked against the template rules.
+    let source = source.replace("ture", "true\ndefault = 0");
+    let path = common::write_schema("typo-template", &[("Typo.toml", &source)]).join("Typo.toml");
+    let errors = gltf_codegen_toml::lint::lint(&path);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].key.as_deref(), Some("fields.count.default"));
+    assert!(errors[0].message.contains("mutually exclusive"), "{}", errors[0]);
+}
+
+#[test]
+fn reports_unknown_keys_of_inline_tables() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Nested"
+module = "misc"
+docs = "Misspelt keys of element types."
+
+[fields.children]
+ty = "Array"
+of = { ty = "Index", off = "misc::Nested" }
+json = "children"
+docs = "Child objects."
+
+[fields.lookup]
+ty = "Map"
+of = { key = "String", value = { ty = "Array", of = { ty = "Integer", size = 2 } } }
+json = "lookup"
+docs = "Named pairs."
+"#;
+    let path = common::write_schema("nested", &[("Nested.toml", source)]).join("Nested.toml");
+    let errors = gltf_codegen_toml::lint::lint(&path);
+    let keys = errors.iter().map(|error| error.key.as_deref().unwrap()).collect::<Vec<_>>();
+    assert_eq!(keys, ["fields.children.of.off", "fields.lookup.of.value.of.size"], "{:#?}", errors);
+    assert_eq!(errors[0].position, Some((9, 1)));
+    assert!(errors[1].message.contains("unknown key `size`"), "{}", errors[1]);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use gltf_codegen_toml::schema::{self, Definition};
+use gltf_codegen_toml::Generator;
+use std::path::Path;
+
+#[test]
+fn reports_template_violations() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Invalid"
+docs = "Optional and default together."
+
+[fields.count]
+ty = "Integer"
+docs = "A count."
+optional = true
+default = 0
+"#;
+    let dir = common::write_schema("invalid", &[("Invalid.toml", source)]);
+    let err = schema::load(&dir.join("Invalid.toml")).unwrap_err();
+    assert_eq!(err.key.as_deref(), Some("fields.count.default"));
+    assert_eq!(err.position, Some((10, 11)));
+    assert!(err.message.contains("mutually exclusive"), "{}", err);
+}
+
+#[test]
+fn preserves_declaration_order() {
+    let definition = schema::load(Path::new("schema/scene/Node.toml")).unwrap();
+    let def = match definition {
+        Definition::Struct(def) => def,
+        _ => panic!("expected a struct definition"),
+    };
+    let fields = def.fields.iter().map(|field| field.ident.as_str()).collect::<Vec<_>>();
+    let expected = ["camera", "children", "matrix", "mesh", "rotation", "scale", "translation", "skin", "weights"];
+    assert_eq!(fields, expected);
+
+    let definition = schema::load(Path::new("schema/animation/Interpolation.toml")).unwrap();
+    let def = match definition {
+        Definition::Enum(def) => def,
+        _ => panic!("expected an enum definition"),
+    };
+    let variants = def.variants.iter().map(|variant| variant.ident.as_str()).collect::<Vec<_>>();
+    assert_eq!(variants, ["Linear", "Step", "CatmullRomSpline", "CubicSpline"]);
+}
+
+#[test]
+fn reports_overlapping_patterns() {
+    let source = r#"[meta]
+kind = "Enum"
+of = "Pattern"
+ident = "Overlapping"
+docs = "Two patterns sharing a prefix."
+
+[values]
+Extras = { value = "_{String}", docs = "Extra name." }
+Numbered = { value = "_N{u32}", docs = "Numbered name." }
+"#;
+    let dir = common::write_schema("overlapping", &[("Overlapping.toml", source)]);
+    let err = schema::load(&dir.join("Overlapping.toml")).unwrap_err();
+    assert_eq!(err.key.as_deref(), Some("values.Numbered.value"));
+    assert_eq!(err.message, "pattern overlaps the pattern of variant `Extras`");
+}
+
+#[test]
+fn reports_union_name_clashes() {
+    let source = r#"[meta]
+kind = "Union"
+ident = "Clashing"
+docs = "A variant named like a field."
+
+[union]
+ident = "shape"
+tag = "type"
+docs = "The shape."
+
+[variants.Box]
+value = "box"
+of = "Cuboid"
+docs = "A box."
+
+[fields.extent]
+ty = "Float"
+json = "box"
+docs = "The extent."
+"#;
+    let dir = common::write_schema("clashing", &[("Clashing.toml", source)]);
+    let err = schema::load(&dir.join("Clashing.toml")).unwrap_err();
+    assert_eq!(err.key.as_deref(), Some("variants.Box.value"));
+    assert_eq!(err.position, Some((12, 9)));
+    assert_eq!(err.message, "JSON name `box` is already used by field `extent`");
+}
+
+#[test]
+fn reports_misplaced_constraints() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Constrained"
+docs = "A bound on a string."
+
+[fields.label]
+ty = "String"
+docs = "A label."
+minimum = 1
+"#;
+    let dir = common::write_schema("constrained", &[("Constrained.toml", source)]);
+    let err = schema::load(&dir.join("Constrained.toml")).unwrap_err();
+    assert_eq!(err.key.as_deref(), Some("fields.label.minimum"));
+    assert_eq!(err.position, Some((9, 11)));
+    assert_eq!(err.message, "`minimum` is only valid for numbers and arrays of numbers");
+}
+
+#[test]
+fn derives_and_checks_json_names() {
+    let source = r#"[meta]
+kind = "Struct"
+ident = "Renamed"
+module = "misc"
+docs = "Fields without JSON names."
+
+[fields.byte_offset]
+ty = "Integer"
+docs = "An offset."
+
+[fields.offset]
+ty = "Integer"
+json = "offset"
+docs = "Another offset."
+"#;
+    let dir = common::write_schema("renamed", &[("Renamed.toml", source)]);
+    let generated = Generator::new(&dir).generate_definition(dir.join("Renamed.toml")).unwrap();
+    assert!(generated.contains("#[serde(rename = \"byteOffset\")]"), "{}", generated);
+    assert!(generated.contains("pub offset: u32,"), "{}", generated);
+    assert!(!generated.contains("rename = \"offset\""), "{}", generated);
+
+    // The camelCase fallback may clash with an explicit name.
+    let source = source.replace("json = \"offset\"", "json = \"byteOffset\"");
+    let dir = common::write_schema("renamed-clash", &[("Renamed.toml", &source)]);
+    let err = schema::load(&dir.join("Renamed.toml")).unwrap_err();
+    assert_eq!(err.key.as_deref(), Some("fields.offset.json"));
+    assert_eq!(err.message, "JSON name `byteOffset` is already used by field `byte_offset`");
+}